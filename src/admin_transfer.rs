@@ -0,0 +1,161 @@
+use cosmwasm_std::{ensure_eq, Addr, DepsMut, Env, Response, StdError};
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
+use injective_math::FPDecimal;
+
+use crate::{
+    error::ContractError,
+    lp_actions::{
+        fees::{accrue_management_fee, update_lot_high_water_marks},
+        lp_token::{
+            get_lp_token_mint_messages, get_lp_token_transfer_message, sync_lp_total_supply,
+            trim_lp_position,
+        },
+        utils::is_admin_owned_share_below_minimum,
+    },
+    state::{
+        PendingAdmin, ADMIN_OWNED_SHARES, CONFIG, IS_FUND_CLOSED, LP_POSITIONS, LP_TOKEN,
+        LP_TOTAL_SUPPLY, PENDING_ADMIN,
+    },
+};
+
+pub fn propose_new_admin(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
+    sender: Addr,
+    new_admin: String,
+    expires_in: u64,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_eq!(sender, config.admin, ContractError::Unauthorized {});
+
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    if new_admin == config.admin {
+        return Err(ContractError::Std(StdError::generic_err(
+            "New admin must differ from the current admin",
+        )));
+    }
+
+    if expires_in == 0 {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Admin proposal must expire in the future",
+        )));
+    }
+
+    // a new proposal replaces any pending one
+    let pending_admin = PendingAdmin {
+        address: new_admin,
+        expiry: env.block.time.plus_seconds(expires_in),
+    };
+    PENDING_ADMIN.save(deps.storage, &pending_admin)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "propose_new_admin")
+        .add_attribute("pending_admin", pending_admin.address)
+        .add_attribute("expiry", pending_admin.expiry.seconds().to_string()))
+}
+
+/// Hands the fund over to the pending admin. Management fees up to now are accrued to the
+/// outgoing admin, who keeps their LP position as a regular LP. From then on only the new
+/// admin's own position counts towards the 10% minimum, so they must hold it before accepting.
+/// With an LP token the fund holds the admin stake: the outgoing admin's stake is sent to them
+/// and the new admin sends theirs in when accepting, keeping only the lots it covers.
+/// Admin fee positions not yet received stay in the fund and go to the new admin.
+pub fn accept_admin(
+    mut deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
+    sender: Addr,
+    lp_tokens_sent: Option<FPDecimal>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::Std(StdError::generic_err(
+            "There is no pending admin",
+        )))?;
+    ensure_eq!(
+        sender,
+        pending_admin.address,
+        ContractError::Unauthorized {}
+    );
+
+    if env.block.time >= pending_admin.expiry {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Admin proposal has expired",
+        )));
+    }
+
+    let lp_token = LP_TOKEN.may_load(deps.storage)?;
+    if lp_token.is_some() != lp_tokens_sent.is_some() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "LP tokens must be sent if and only if the fund has an LP token",
+        )));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    sync_lp_total_supply(deps.branch())?;
+    let management_fee_shares = accrue_management_fee(deps.branch(), env, &config)?;
+    let mut lp_token_messages =
+        get_lp_token_mint_messages(deps.storage, env, management_fee_shares, &config.admin)?;
+
+    if let Some(lp_token) = lp_token.as_ref() {
+        let previous_admin_owned_shares = ADMIN_OWNED_SHARES
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        if !previous_admin_owned_shares.is_zero() {
+            lp_token_messages.push(get_lp_token_transfer_message(
+                lp_token,
+                previous_admin_owned_shares,
+                &config.admin,
+            )?);
+        }
+    }
+
+    let lp_position = LP_POSITIONS
+        .may_load(deps.storage, &sender)?
+        .unwrap_or_default();
+    let (admin_lp_position, admin_owned_shares) = match lp_tokens_sent {
+        Some(lp_tokens_sent) => (
+            trim_lp_position(lp_position.to_owned(), lp_tokens_sent),
+            lp_tokens_sent,
+        ),
+        None => (lp_position.to_owned(), lp_position.shares()),
+    };
+    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)?;
+    let is_fund_closed = IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default();
+
+    if !is_fund_closed && is_admin_owned_share_below_minimum(admin_owned_shares, lp_total_supply) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "New admin must own at least 10% of fund",
+        )));
+    }
+
+    // the new admin's lots stop being charged performance fees and the previous admin's start
+    update_lot_high_water_marks(deps.storage, &config, &sender, &lp_position.lots, false)?;
+    if admin_lp_position.lots.is_empty() {
+        LP_POSITIONS.remove(deps.storage, &sender);
+    } else {
+        LP_POSITIONS.save(deps.storage, &sender, &admin_lp_position)?;
+    }
+
+    let previous_admin = config.admin;
+    config.admin = sender;
+    CONFIG.save(deps.storage, &config)?;
+
+    let previous_admin_lp_position = LP_POSITIONS
+        .may_load(deps.storage, &previous_admin)?
+        .unwrap_or_default();
+    update_lot_high_water_marks(
+        deps.storage,
+        &config,
+        &previous_admin,
+        &previous_admin_lp_position.lots,
+        true,
+    )?;
+    ADMIN_OWNED_SHARES.save(deps.storage, &admin_owned_shares)?;
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_messages(lp_token_messages)
+        .add_attribute("method", "accept_admin")
+        .add_attribute("previous_admin", previous_admin)
+        .add_attribute("admin", config.admin))
+}
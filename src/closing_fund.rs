@@ -1,29 +1,40 @@
 #[cfg(not(feature = "library"))]
-use cosmwasm_std::{ensure_eq, Addr, DepsMut, Response};
+use cosmwasm_std::{ensure_eq, Addr, DepsMut, Env, Response};
 use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQuerier, InjectiveQueryWrapper};
 
 use crate::{
     error::ContractError,
-    state::{CONFIG, IS_FUND_CLOSED},
+    lp_actions::{
+        fees::accrue_management_fee,
+        lp_token::{get_lp_token_mint_messages, sync_lp_total_supply},
+    },
+    roles::ensure_role,
+    state::{Role, CONFIG, IS_FUND_CLOSED},
 };
 
 pub fn close_fund(
-    deps: DepsMut<InjectiveQueryWrapper>,
+    mut deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
     sender: Addr,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    ensure_eq!(sender, config.admin, ContractError::Unauthorized {});
+    ensure_role(deps.storage, &config, &sender, Role::Guardian)?;
 
     let querier = InjectiveQuerier::new(&deps.querier);
 
-    for (_, market_id) in config.derivative_market_ids.iter().enumerate() {
+    for market_id in config.derivative_market_ids.iter() {
         let vault_position = querier
             .query_vanilla_subaccount_position(market_id, &config.fund_subaccount_id)?
             .state;
         ensure_eq!(vault_position, None, ContractError::NonZeroVaultPosition {});
     }
 
+    sync_lp_total_supply(deps.branch())?;
+    let management_fee_shares = accrue_management_fee(deps.branch(), env, &config)?;
+    let mint_messages =
+        get_lp_token_mint_messages(deps.storage, env, management_fee_shares, &config.admin)?;
+
     IS_FUND_CLOSED.save(deps.storage, &true)?;
 
-    Ok(Response::new())
+    Ok(Response::new().add_messages(mint_messages))
 }
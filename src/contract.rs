@@ -3,24 +3,58 @@ use std::collections::{HashMap, HashSet};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    ensure_eq, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
-    StdError, StdResult,
+    from_binary, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order, Reply,
+    Response, StdError, StdResult,
 };
 use injective_cosmwasm::privileged_action::{PositionTransferAction, PrivilegedAction};
 use injective_cosmwasm::{
-    InjectiveMsg, InjectiveMsgWrapper, InjectiveQuerier, InjectiveQueryWrapper, SubaccountId,
+    InjectiveMsgWrapper, InjectiveQuerier, InjectiveQueryWrapper, MarketId, SubaccountId,
 };
 use injective_math::FPDecimal;
 
+use crate::admin_transfer::{accept_admin, propose_new_admin};
 use crate::closing_fund::close_fund;
 use crate::error::ContractError;
-use crate::lp_actions::redemptions::get_fund_redemption_response;
+use crate::lp_actions::fees::{crystallize_fees, MAX_CRYSTALLIZATION_PERIOD};
+use crate::lp_actions::lp_token::{
+    ensure_lp_tokens_not_sent_by_admin, get_lp_tokens_sent, get_sender_lp_tokens_sent,
+    setup_lp_token, INSTANTIATE_CW20_LP_TOKEN_REPLY_ID,
+};
+use crate::lp_actions::redemptions::{
+    ensure_valid_early_redemption_policy, get_fund_redemption_response, MAX_LOCKUP_PERIOD,
+};
 use crate::lp_actions::subscriptions::get_fund_subscription_response;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::lp_actions::transfers::{
+    get_fund_transfer_response, get_lp_token_registration_response,
+};
+use crate::migrations::run_migrations;
+use crate::msg::{
+    Cw20HookMsg, DrawdownBreakerResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg,
+};
+use crate::nav_history::{is_nav_snapshot_due, store_nav_snapshot};
+use crate::pause::{ensure_not_paused, pause, unpause};
+use crate::queries::{
+    query_accrued_management_fee, query_all_admin_fee_positions, query_all_lp_positions,
+    query_crystallizations, query_denom_decimals, query_fund_state, query_lp_position, query_nav,
+    query_nav_history, query_simulate_redeem, query_simulate_subscribe,
+};
+use crate::risk::drawdown::{
+    ensure_risk_reducing_messages, get_current_nav_per_share, reset_drawdown_breaker,
+    update_drawdown_breaker,
+};
+use crate::risk::order_policy::{
+    ensure_messages_within_risk_policy, ensure_valid_risk_policy, update_risk_policy,
+};
+use crate::roles::{ensure_role, get_roles, update_roles};
 use crate::state::{
-    Config, ADMIN_FEE_POSITIONS, CONFIG, DENOM_DECIMALS, IS_FUND_CLOSED, LP_TOTAL_SUPPLY,
+    Config, EarlyRedemptionPolicy, LpToken, Role, ADMIN_FEE_POSITIONS, ADMIN_OWNED_SHARES, CONFIG,
+    DENOM_DECIMALS, DRAWDOWN_TRIP, IS_FUND_CLOSED, LAST_CRYSTALLIZATION_TIME,
+    LAST_MANAGEMENT_FEE_ACCRUAL, LP_TOKEN, LP_TOTAL_SUPPLY, PAUSE_STATE, PEAK_NAV_PER_SHARE,
+    PENDING_ADMIN, RISK_POLICY,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::Cw20ReceiveMsg;
+use cw_utils::parse_reply_instantiate_data;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:injective:dummy";
@@ -29,7 +63,7 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut<InjectiveQueryWrapper>,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
@@ -44,11 +78,40 @@ pub fn instantiate(
             quote_denom: msg.quote_denom.to_owned(),
             fund_subaccount_id: msg.fund_subaccount_id,
             performance_fee_rate: msg.performance_fee_rate,
+            management_fee_rate: msg.management_fee_rate,
             min_yearly_roi_for_fees: msg.min_yearly_roi_for_fees,
+            lockup_period: msg.lockup_period,
+            early_redemption_policy: msg.early_redemption_policy.to_owned(),
+            performance_fee_collection: msg.performance_fee_collection.to_owned(),
+            crystallization_period: msg.crystallization_period,
         },
     )?;
 
+    if msg.lockup_period > MAX_LOCKUP_PERIOD {
+        return Err(ContractError::InvalidLockupPeriod {});
+    }
+
+    ensure_valid_risk_policy(&CONFIG.load(deps.storage)?, &msg.risk_policy)?;
+    RISK_POLICY.save(deps.storage, &msg.risk_policy)?;
+
+    ensure_valid_early_redemption_policy(deps.api, &msg.early_redemption_policy)?;
+
+    if msg.performance_fee_rate < FPDecimal::zero() || msg.performance_fee_rate > FPDecimal::one() {
+        return Err(ContractError::InvalidPerformanceFeeRate {});
+    }
+
+    if msg.management_fee_rate < FPDecimal::zero() || msg.management_fee_rate >= FPDecimal::one() {
+        return Err(ContractError::InvalidManagementFeeRate {});
+    }
+
+    if msg.crystallization_period > MAX_CRYSTALLIZATION_PERIOD {
+        return Err(ContractError::InvalidCrystallizationPeriod {});
+    }
+
     LP_TOTAL_SUPPLY.save(deps.storage, &FPDecimal::zero())?;
+    ADMIN_OWNED_SHARES.save(deps.storage, &FPDecimal::zero())?;
+    LAST_MANAGEMENT_FEE_ACCRUAL.save(deps.storage, &env.block.time)?;
+    LAST_CRYSTALLIZATION_TIME.save(deps.storage, &env.block.time)?;
 
     let querier = InjectiveQuerier::new(&deps.querier);
 
@@ -63,7 +126,7 @@ pub fn instantiate(
     let mut denoms = HashSet::new();
     denoms.insert(msg.quote_denom.to_owned());
 
-    for (_, market_id) in msg.spot_market_ids.iter().enumerate() {
+    for market_id in msg.spot_market_ids.iter() {
         let market_res = querier
             .query_spot_market(market_id)
             .expect("spot market {market_id} not found in query");
@@ -78,7 +141,7 @@ pub fn instantiate(
         }
     }
 
-    for (_, market_id) in msg.derivative_market_ids.iter().enumerate() {
+    for market_id in msg.derivative_market_ids.iter() {
         let market_res = querier
             .query_derivative_market(market_id)
             .expect("derivative market {market_id} not found in query");
@@ -103,7 +166,13 @@ pub fn instantiate(
 
     DENOM_DECIMALS.save(deps.storage, &denom_hash_map)?;
 
+    let lp_token_messages = match msg.lp_token {
+        Some(lp_token_instantiation) => setup_lp_token(deps, &env, lp_token_instantiation)?,
+        None => vec![],
+    };
+
     Ok(Response::new()
+        .add_submessages(lp_token_messages)
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender))
 }
@@ -117,38 +186,301 @@ pub fn execute(
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
     match msg {
         ExecuteMsg::AdminExecuteMessages { injective_messages } => {
-            execute_messages(deps, info.sender, injective_messages)
+            execute_messages(deps, &env, info.sender, injective_messages)
         }
         ExecuteMsg::Subscribe {} => {
             get_fund_subscription_response(deps, &env, &info.sender, info.funds)
         }
         ExecuteMsg::Redeem {
             redeemer_subaccount_id,
-        } => get_fund_redemption_response(deps, &env, &info.sender, redeemer_subaccount_id),
+            shares,
+            lot_ids,
+            allow_early_redemption,
+        } => {
+            let lp_tokens_sent =
+                get_sender_lp_tokens_sent(deps.storage, &info.sender, &info.funds)?;
+
+            get_fund_redemption_response(
+                deps,
+                &env,
+                &info.sender,
+                redeemer_subaccount_id,
+                shares,
+                lot_ids,
+                allow_early_redemption,
+                lp_tokens_sent,
+            )
+        }
         ExecuteMsg::AdminReceiveFeePositions {
             receiving_subaccount_id,
         } => admin_receive_fee_positions(deps, info.sender, receiving_subaccount_id),
-        ExecuteMsg::CloseFund {} => close_fund(deps, info.sender),
+        ExecuteMsg::CloseFund {} => close_fund(deps, &env, info.sender),
+        ExecuteMsg::UpdateLockupPeriod { lockup_period } => {
+            update_lockup_period(deps, info.sender, lockup_period)
+        }
+        ExecuteMsg::UpdateEarlyRedemptionPolicy {
+            early_redemption_policy,
+        } => update_early_redemption_policy(deps, info.sender, early_redemption_policy),
+        ExecuteMsg::UpdateCrystallizationPeriod {
+            crystallization_period,
+        } => update_crystallization_period(deps, info.sender, crystallization_period),
+        ExecuteMsg::Receive(cw20_receive_msg) => receive_cw20(deps, env, info, cw20_receive_msg),
+        ExecuteMsg::ProposeNewAdmin {
+            new_admin,
+            expires_in,
+        } => propose_new_admin(deps, &env, info.sender, new_admin, expires_in),
+        ExecuteMsg::AcceptAdmin {} => {
+            let lp_tokens_sent = LP_TOKEN
+                .may_load(deps.storage)?
+                .map(|lp_token| get_lp_tokens_sent(&lp_token, &info.funds))
+                .transpose()?;
+
+            accept_admin(deps, &env, info.sender, lp_tokens_sent)
+        }
+        ExecuteMsg::UpdateRoles {
+            trader,
+            operator,
+            guardian,
+        } => update_roles(deps, info.sender, trader, operator, guardian),
+        ExecuteMsg::Pause {
+            reason,
+            block_redemptions,
+        } => pause(deps, &env, info.sender, reason, block_redemptions),
+        ExecuteMsg::Unpause {} => unpause(deps, info.sender),
+        ExecuteMsg::UpdateRiskPolicy { risk_policy } => {
+            update_risk_policy(deps, info.sender, risk_policy)
+        }
+        ExecuteMsg::ResetDrawdownBreaker {} => reset_drawdown_breaker(deps, info.sender),
+        ExecuteMsg::TransferPosition { recipient, shares } => {
+            let recipient = deps.api.addr_validate(&recipient)?;
+            let lp_tokens_sent =
+                get_sender_lp_tokens_sent(deps.storage, &info.sender, &info.funds)?;
+
+            get_fund_transfer_response(deps, &info.sender, &recipient, shares, lp_tokens_sent)
+        }
+        ExecuteMsg::RegisterLpTokens {} => {
+            get_lp_token_registration_response(deps, &env, &info.sender)
+        }
+    }
+}
+
+pub fn receive_cw20(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    cw20_receive_msg: Cw20ReceiveMsg,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    // only the fund's own CW20 LP token can be sent in
+    match LP_TOKEN.may_load(deps.storage)? {
+        Some(LpToken::Cw20 { address }) if address == info.sender => {}
+        _ => return Err(ContractError::Unauthorized {}),
+    }
+
+    let sender = deps.api.addr_validate(&cw20_receive_msg.sender)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    match from_binary(&cw20_receive_msg.msg)? {
+        Cw20HookMsg::Redeem {
+            redeemer_subaccount_id,
+            lot_ids,
+            allow_early_redemption,
+        } => {
+            ensure_lp_tokens_not_sent_by_admin(&config, &sender)?;
+            get_fund_redemption_response(
+                deps,
+                &env,
+                &sender,
+                redeemer_subaccount_id,
+                None,
+                lot_ids,
+                allow_early_redemption,
+                Some(cw20_receive_msg.amount.into()),
+            )
+        }
+        Cw20HookMsg::TransferPosition { recipient } => {
+            ensure_lp_tokens_not_sent_by_admin(&config, &sender)?;
+            let recipient = deps.api.addr_validate(&recipient)?;
+            get_fund_transfer_response(
+                deps,
+                &sender,
+                &recipient,
+                cw20_receive_msg.amount.into(),
+                Some(cw20_receive_msg.amount.into()),
+            )
+        }
+        Cw20HookMsg::AcceptAdmin {} => {
+            accept_admin(deps, &env, sender, Some(cw20_receive_msg.amount.into()))
+        }
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    msg: Reply,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    match msg.id {
+        INSTANTIATE_CW20_LP_TOKEN_REPLY_ID => {
+            let instantiate_response = parse_reply_instantiate_data(msg)
+                .map_err(|err| ContractError::Std(StdError::generic_err(err.to_string())))?;
+            let address = deps
+                .api
+                .addr_validate(&instantiate_response.contract_address)?;
+
+            LP_TOKEN.save(
+                deps.storage,
+                &LpToken::Cw20 {
+                    address: address.to_owned(),
+                },
+            )?;
+
+            Ok(Response::new()
+                .add_attribute("method", "instantiate_cw20_lp_token")
+                .add_attribute("lp_token", address))
+        }
+        _ => Err(ContractError::Std(StdError::generic_err(format!(
+            "Unknown reply id {}",
+            msg.id
+        )))),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(
+    mut deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    match msg {
+        SudoMsg::BeginBlocker {} => {
+            let config = CONFIG.load(deps.storage)?;
+            let is_fund_closed = IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default();
+            let last_crystallization_time = LAST_CRYSTALLIZATION_TIME
+                .may_load(deps.storage)?
+                .unwrap_or(env.block.time);
+
+            let is_crystallization_due = config.crystallization_period > 0
+                && env.block.time
+                    >= last_crystallization_time.plus_seconds(config.crystallization_period);
+            if is_fund_closed {
+                return Ok(Response::new());
+            }
+
+            let mut response = Response::new();
+            if is_crystallization_due {
+                response = crystallize_fees(deps.branch(), &env, &config)?;
+            }
+
+            if is_crystallization_due || is_nav_snapshot_due(deps.as_ref(), &env)? {
+                store_nav_snapshot(deps, &env, &config)?;
+            }
+
+            Ok(response)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let stored_version = get_contract_version(deps.storage)?;
+    if stored_version.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidContractName {});
+    }
+
+    run_migrations(
+        deps.storage,
+        &env,
+        &stored_version.version,
+        CONTRACT_VERSION,
+    )?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored_version.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+pub fn update_crystallization_period(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    sender: Addr,
+    crystallization_period: u64,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &config, &sender, Role::Operator)?;
+
+    if crystallization_period > MAX_CRYSTALLIZATION_PERIOD {
+        return Err(ContractError::InvalidCrystallizationPeriod {});
+    }
+
+    config.crystallization_period = crystallization_period;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_crystallization_period")
+        .add_attribute("crystallization_period", crystallization_period.to_string()))
+}
+
+pub fn update_early_redemption_policy(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    sender: Addr,
+    early_redemption_policy: Option<EarlyRedemptionPolicy>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &config, &sender, Role::Operator)?;
+
+    ensure_valid_early_redemption_policy(deps.api, &early_redemption_policy)?;
+
+    config.early_redemption_policy = early_redemption_policy;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("method", "update_early_redemption_policy"))
+}
+
+pub fn update_lockup_period(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    sender: Addr,
+    lockup_period: u64,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &config, &sender, Role::Operator)?;
+
+    if lockup_period > MAX_LOCKUP_PERIOD {
+        return Err(ContractError::InvalidLockupPeriod {});
+    }
+
+    config.lockup_period = lockup_period;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_lockup_period")
+        .add_attribute("lockup_period", lockup_period.to_string()))
+}
+
 pub fn admin_receive_fee_positions(
     deps: DepsMut<InjectiveQueryWrapper>,
     sender: Addr,
     receiving_subaccount_id: SubaccountId,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    ensure_eq!(sender, config.admin, ContractError::Unauthorized {});
+    ensure_role(deps.storage, &config, &sender, Role::Operator)?;
 
-    let admin_fee_positions = ADMIN_FEE_POSITIONS.load(deps.storage)?;
+    let admin_fee_positions = ADMIN_FEE_POSITIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
 
     let mut response = Response::new();
 
-    for (_, (market_id, quantity)) in admin_fee_positions.iter().enumerate() {
+    for (market_id, quantity) in admin_fee_positions.iter() {
         let privileged_action = PrivilegedAction {
             synthetic_trade: None,
             position_transfer: Some(PositionTransferAction {
-                market_id: market_id.to_owned(),
+                market_id: MarketId::unchecked(market_id),
                 source_subaccount_id: config.fund_subaccount_id.to_owned(),
                 destination_subaccount_id: receiving_subaccount_id.to_owned(),
                 quantity: quantity.to_owned(),
@@ -162,43 +494,117 @@ pub fn admin_receive_fee_positions(
 
 pub fn execute_messages(
     deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
     sender: Addr,
     msgs: Vec<CosmosMsg<InjectiveMsgWrapper>>,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    ensure_eq!(sender, config.admin, ContractError::Unauthorized {});
+    ensure_role(deps.storage, &config, &sender, Role::Trader)?;
 
     let is_fund_closed = IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default();
     if is_fund_closed {
         return Err(ContractError::Std(StdError::generic_err("Fund is closed")));
     }
 
-    if !are_messages_authorized(msgs.iter().collect()) {
-        return Err(ContractError::Unauthorized {});
+    ensure_not_paused(deps.storage, false)?;
+
+    let projected_exposure =
+        ensure_messages_within_risk_policy(deps.as_ref(), env, &config, &msgs)?;
+
+    let is_drawdown_tracked = RISK_POLICY
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .max_drawdown
+        .is_some();
+    let drawdown_trip = if is_drawdown_tracked {
+        let nav_per_share = get_current_nav_per_share(deps.as_ref(), &config)?;
+        update_drawdown_breaker(deps.storage, env, nav_per_share)?
+    } else {
+        DRAWDOWN_TRIP.may_load(deps.storage)?
+    };
+    if drawdown_trip.is_some() {
+        ensure_risk_reducing_messages(&msgs)?;
     }
 
-    Ok(Response::new().add_messages(msgs))
-}
-
-fn are_messages_authorized(msgs: Vec<&CosmosMsg<InjectiveMsgWrapper>>) -> bool {
-    msgs.into_iter().all(is_message_authorized)
-}
-
-fn is_message_authorized(msg: &CosmosMsg<InjectiveMsgWrapper>) -> bool {
-    // TODO add more message validations, e.g. prevent out-of-range market orders
+    let mut response = Response::new().add_messages(msgs);
+    if let Some(projected_exposure) = projected_exposure {
+        response = response
+            .add_attribute("nav", projected_exposure.nav.to_string())
+            .add_attribute(
+                "projected_gross_exposure",
+                projected_exposure.gross_exposure.to_string(),
+            )
+            .add_attribute(
+                "projected_net_exposure",
+                projected_exposure.net_exposure.to_string(),
+            );
+    }
 
-    matches!(
-        msg,
-        CosmosMsg::Custom(InjectiveMsgWrapper {
-            msg_data: InjectiveMsg::BatchUpdateOrders { .. },
-            ..
-        })
-    )
+    Ok(response)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(_deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    msg: QueryMsg,
+) -> Result<Binary, ContractError> {
     match msg {
-        QueryMsg::Ping { .. } => to_binary("pong"),
+        QueryMsg::Ping { .. } => Ok(to_binary("pong")?),
+        QueryMsg::Config {} => Ok(to_binary(&CONFIG.load(deps.storage)?)?),
+        QueryMsg::PendingAdmin {} => Ok(to_binary(&PENDING_ADMIN.may_load(deps.storage)?)?),
+        QueryMsg::Roles {} => Ok(to_binary(&get_roles(
+            deps.storage,
+            &CONFIG.load(deps.storage)?,
+        )?)?),
+        QueryMsg::PauseState {} => Ok(to_binary(&PAUSE_STATE.may_load(deps.storage)?)?),
+        QueryMsg::RiskPolicy {} => Ok(to_binary(
+            &RISK_POLICY.may_load(deps.storage)?.unwrap_or_default(),
+        )?),
+        QueryMsg::DrawdownBreaker {} => Ok(to_binary(&DrawdownBreakerResponse {
+            peak_nav_per_share: PEAK_NAV_PER_SHARE.may_load(deps.storage)?,
+            drawdown_trip: DRAWDOWN_TRIP.may_load(deps.storage)?,
+        })?),
+        QueryMsg::FundState {} => Ok(to_binary(&query_fund_state(deps)?)?),
+        QueryMsg::DenomDecimals {} => Ok(to_binary(&query_denom_decimals(deps)?)?),
+        QueryMsg::Nav {} => Ok(to_binary(&query_nav(deps, &env)?)?),
+        QueryMsg::AccruedManagementFee {} => {
+            Ok(to_binary(&query_accrued_management_fee(deps, &env)?)?)
+        }
+        QueryMsg::NavHistory { start_after, limit } => {
+            Ok(to_binary(&query_nav_history(deps, start_after, limit)?)?)
+        }
+        QueryMsg::Crystallizations { start_after, limit } => Ok(to_binary(
+            &query_crystallizations(deps, start_after, limit)?,
+        )?),
+        QueryMsg::LpPosition { address } => {
+            Ok(to_binary(&query_lp_position(deps, &env, address)?)?)
+        }
+        QueryMsg::AllLpPositions { start_after, limit } => Ok(to_binary(&query_all_lp_positions(
+            deps,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::AllAdminFeePositions { start_after, limit } => Ok(to_binary(
+            &query_all_admin_fee_positions(deps, start_after, limit)?,
+        )?),
+        QueryMsg::SimulateRedeem {
+            address,
+            redeemer_subaccount_id,
+            shares,
+            lot_ids,
+            allow_early_redemption,
+        } => Ok(to_binary(&query_simulate_redeem(
+            deps,
+            &env,
+            address,
+            redeemer_subaccount_id,
+            shares,
+            lot_ids,
+            allow_early_redemption,
+        )?)?),
+        QueryMsg::SimulateSubscribe { amount, subscriber } => Ok(to_binary(
+            &query_simulate_subscribe(deps, &env, amount, subscriber)?,
+        )?),
     }
 }
@@ -9,6 +9,12 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("InvalidContractName")]
+    InvalidContractName {},
+
+    #[error("InvalidContractVersion")]
+    InvalidContractVersion {},
+
     #[error("NoMarketsProvided")]
     NoMarketsProvided {},
 
@@ -20,4 +26,28 @@ pub enum ContractError {
 
     #[error("NonZeroVaultPosition")]
     NonZeroVaultPosition {},
+
+    #[error("InvalidLockupPeriod")]
+    InvalidLockupPeriod {},
+
+    #[error("InvalidEarlyRedemptionPolicy")]
+    InvalidEarlyRedemptionPolicy {},
+
+    #[error("InvalidPerformanceFeeRate")]
+    InvalidPerformanceFeeRate {},
+
+    #[error("InvalidManagementFeeRate")]
+    InvalidManagementFeeRate {},
+
+    #[error("InvalidCrystallizationPeriod")]
+    InvalidCrystallizationPeriod {},
+
+    #[error("FundPaused")]
+    FundPaused {},
+
+    #[error("InvalidRiskPolicy")]
+    InvalidRiskPolicy {},
+
+    #[error("DrawdownBreakerTripped")]
+    DrawdownBreakerTripped {},
 }
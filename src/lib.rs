@@ -1,8 +1,15 @@
+pub mod admin_transfer;
 pub mod closing_fund;
 pub mod contract;
 mod error;
 pub mod lp_actions;
+pub mod migrations;
 pub mod msg;
+pub mod nav_history;
+pub mod pause;
+pub mod queries;
+pub mod risk;
+pub mod roles;
 pub mod state;
 
 pub use crate::error::ContractError;
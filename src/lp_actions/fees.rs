@@ -0,0 +1,1018 @@
+use cosmwasm_std::{
+    Addr, Coin, Deps, DepsMut, Env, Event, Order, Response, StdResult, Storage, Timestamp, Uint128,
+};
+use cw_storage_plus::Bound;
+use injective_cosmwasm::{
+    privileged_action::PositionTransferAction, InjectiveMsgWrapper, InjectiveQuerier,
+    InjectiveQueryWrapper, MarketId,
+};
+use injective_math::FPDecimal;
+
+use crate::{
+    risk::drawdown::rebase_peak_nav_per_share,
+    state::{
+        Config, Crystallization, LPLot, LPPosition, PerformanceFeeCollection, ADMIN_OWNED_SHARES,
+        CRYSTALLIZATIONS, CRYSTALLIZATION_COUNT, DENOM_DECIMALS, FUND_HIGH_WATER_MARK,
+        IS_FUND_CLOSED, LAST_CRYSTALLIZATION_TIME, LAST_MANAGEMENT_FEE_ACCRUAL,
+        LOT_HIGH_WATER_MARKS, LP_LOT_COUNT, LP_POSITIONS, LP_TOTAL_SUPPLY,
+    },
+    ContractError,
+};
+
+use super::{
+    lp_token::{get_lp_token_mint_messages, round_lp_shares, sync_lp_total_supply},
+    redemptions::{get_profits_per_year, should_charge_performance_fees, ONE_YEAR_IN_SECONDS},
+    utils::{get_fund_total_notional, get_nav_per_share},
+};
+
+pub const MAX_CRYSTALLIZATION_PERIOD: u64 = ONE_YEAR_IN_SECONDS;
+
+pub fn get_accrued_management_fee_shares(
+    env: &Env,
+    config: &Config,
+    lp_total_supply: FPDecimal,
+    last_accrual_time: Timestamp,
+) -> FPDecimal {
+    if lp_total_supply.is_zero() || env.block.time <= last_accrual_time {
+        return FPDecimal::zero();
+    }
+
+    let time_since_accrual = env.block.time.seconds() - last_accrual_time.seconds();
+    let fee_share_of_fund = (config.management_fee_rate
+        * FPDecimal::from(time_since_accrual as u128)
+        / FPDecimal::from(ONE_YEAR_IN_SECONDS as u128))
+    .min(FPDecimal::one() / FPDecimal::TWO);
+
+    // mint enough shares for the admin to own fee_share_of_fund of the fund after dilution
+    lp_total_supply * fee_share_of_fund / (FPDecimal::one() - fee_share_of_fund)
+}
+
+pub fn get_pending_management_fee_shares(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    config: &Config,
+) -> StdResult<FPDecimal> {
+    // the fee is accrued one last time when the fund closes
+    if IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default() {
+        return Ok(FPDecimal::zero());
+    }
+
+    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)?;
+
+    let fee_shares = match LAST_MANAGEMENT_FEE_ACCRUAL.may_load(deps.storage)? {
+        Some(last_accrual_time) => {
+            get_accrued_management_fee_shares(env, config, lp_total_supply, last_accrual_time)
+        }
+        None => FPDecimal::zero(),
+    };
+
+    round_lp_shares(deps.storage, fee_shares)
+}
+
+/// Mints the management fee accrued since the last accrual to the admin as a new lot,
+/// priced at the current NAV per share so the admin is not charged performance fees on it.
+pub fn accrue_management_fee(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
+    config: &Config,
+) -> Result<FPDecimal, ContractError> {
+    let fee_shares = get_pending_management_fee_shares(deps.as_ref(), env, config)?;
+    LAST_MANAGEMENT_FEE_ACCRUAL.save(deps.storage, &env.block.time)?;
+
+    if fee_shares.is_zero() {
+        return Ok(fee_shares);
+    }
+
+    let querier = InjectiveQuerier::new(&deps.querier);
+    let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
+    let fund_total_notional = get_fund_total_notional(&querier, config, &denom_decimals, None)?;
+
+    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)?;
+    rebase_peak_nav_per_share(deps.storage, lp_total_supply, fee_shares)?;
+
+    let new_lp_total_supply = lp_total_supply + fee_shares;
+    LP_TOTAL_SUPPLY.save(deps.storage, &new_lp_total_supply)?;
+
+    let admin_owned_shares = ADMIN_OWNED_SHARES
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    ADMIN_OWNED_SHARES.save(deps.storage, &(admin_owned_shares + fee_shares))?;
+
+    let lot_id = LP_LOT_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    LP_LOT_COUNT.save(deps.storage, &lot_id)?;
+
+    let nav_per_share = get_nav_per_share(fund_total_notional, new_lp_total_supply);
+
+    let mut admin_lp_position = LP_POSITIONS
+        .may_load(deps.storage, &config.admin)?
+        .unwrap_or_default();
+    admin_lp_position.lots.push(LPLot {
+        lot_id,
+        shares: fee_shares,
+        subscription_time: env.block.time,
+        subscription_amount: fee_shares * nav_per_share,
+        high_water_mark: nav_per_share,
+    });
+    LP_POSITIONS.save(deps.storage, &config.admin, &admin_lp_position)?;
+
+    Ok(fee_shares)
+}
+
+/// Shares owned by the admin are never charged performance fees, the admin would only be
+/// paying the fee to itself.
+pub fn is_charged_performance_fees(config: &Config, address: &Addr) -> bool {
+    address != &config.admin
+}
+
+/// Marks are keyed by their value in 10^-18 units, which keeps them ordered.
+pub fn get_high_water_mark_key(high_water_mark: FPDecimal) -> u128 {
+    (high_water_mark * FPDecimal::from(10u128.pow(FPDecimal::DIGITS as u32))).into()
+}
+
+fn get_high_water_mark_from_key(key: u128) -> FPDecimal {
+    FPDecimal::from(key) / FPDecimal::from(10u128.pow(FPDecimal::DIGITS as u32))
+}
+
+/// Adds or removes the shares of the charged lots whose mark is above the fund mark, the
+/// other lots are crystallized from the fund mark.
+pub fn update_lot_high_water_marks(
+    storage: &mut dyn Storage,
+    config: &Config,
+    owner: &Addr,
+    lots: &[LPLot],
+    is_added: bool,
+) -> StdResult<()> {
+    if !is_charged_performance_fees(config, owner) {
+        return Ok(());
+    }
+
+    let fund_high_water_mark = FUND_HIGH_WATER_MARK.may_load(storage)?.unwrap_or_default();
+    for lot in lots
+        .iter()
+        .filter(|lot| lot.high_water_mark > fund_high_water_mark)
+    {
+        let key = get_high_water_mark_key(lot.high_water_mark);
+        let shares = LOT_HIGH_WATER_MARKS
+            .may_load(storage, key)?
+            .unwrap_or_default();
+        let shares = if is_added {
+            shares + lot.shares
+        } else {
+            shares - lot.shares
+        };
+
+        if shares > FPDecimal::zero() {
+            LOT_HIGH_WATER_MARKS.save(storage, key, &shares)?;
+        } else {
+            LOT_HIGH_WATER_MARKS.remove(storage, key);
+        }
+    }
+
+    Ok(())
+}
+
+/// Saves an LP position, or removes it once it has no lots, replacing the lot marks of the
+/// position stored before with its own.
+pub fn save_lp_position(
+    storage: &mut dyn Storage,
+    config: &Config,
+    address: &Addr,
+    lp_position: &LPPosition,
+) -> StdResult<()> {
+    if let Some(stored_lp_position) = LP_POSITIONS.may_load(storage, address)? {
+        update_lot_high_water_marks(storage, config, address, &stored_lp_position.lots, false)?;
+    }
+    update_lot_high_water_marks(storage, config, address, &lp_position.lots, true)?;
+
+    if lp_position.lots.is_empty() {
+        LP_POSITIONS.remove(storage, address);
+    } else {
+        LP_POSITIONS.save(storage, address, lp_position)?;
+    }
+
+    Ok(())
+}
+
+/// Gains charged at a crystallization, and the value at the marks they are measured from.
+/// Shares at the fund mark gain what the NAV per share is above it, while the lots with a
+/// higher mark, given as mark and shares, only gain what it is above their own mark.
+pub fn get_crystallization_profits(
+    nav_per_share: FPDecimal,
+    fund_high_water_mark: Option<FPDecimal>,
+    charged_shares: FPDecimal,
+    lot_high_water_marks: &[(FPDecimal, FPDecimal)],
+) -> (FPDecimal, FPDecimal) {
+    let mut profits = FPDecimal::zero();
+    let mut marked_value = FPDecimal::zero();
+    let mut lot_shares = FPDecimal::zero();
+
+    for (high_water_mark, shares) in lot_high_water_marks.iter() {
+        if nav_per_share > *high_water_mark {
+            profits += (nav_per_share - *high_water_mark) * *shares;
+        }
+        marked_value += *high_water_mark * *shares;
+        lot_shares += *shares;
+    }
+
+    // without a fund mark every lot has its own, see migrate_v1_1_0
+    if let Some(fund_high_water_mark) = fund_high_water_mark {
+        let fund_mark_shares = (charged_shares - lot_shares).max(FPDecimal::zero());
+        if nav_per_share > fund_high_water_mark {
+            profits += (nav_per_share - fund_high_water_mark) * fund_mark_shares;
+        }
+        marked_value += fund_high_water_mark * fund_mark_shares;
+    }
+
+    (profits, marked_value)
+}
+
+/// Performance fee in quote notional, never negative and never more than `rate * profits`.
+pub fn get_performance_fee(profits: FPDecimal, performance_fee_rate: FPDecimal) -> FPDecimal {
+    if profits <= FPDecimal::zero() {
+        return FPDecimal::zero();
+    }
+
+    profits
+        * performance_fee_rate
+            .max(FPDecimal::zero())
+            .min(FPDecimal::one())
+}
+
+/// The assets a fee is taken from: each coin or position transfer with its quote notional.
+pub struct FeeAssets {
+    pub funds: Vec<(Coin, FPDecimal)>,
+    pub position_transfers: Vec<(PositionTransferAction, FPDecimal)>,
+}
+
+#[derive(Default)]
+pub struct FeeAllocation {
+    pub funds: Vec<Coin>,
+    pub position_quantities: Vec<(MarketId, FPDecimal)>,
+}
+
+impl FeeAllocation {
+    pub fn get_amount(&self, denom: &str) -> Uint128 {
+        self.funds
+            .iter()
+            .find(|coin| coin.denom == denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default()
+    }
+
+    pub fn get_quantity(&self, market_id: &MarketId) -> FPDecimal {
+        self.position_quantities
+            .iter()
+            .find(|(quantity_market_id, _)| quantity_market_id == market_id)
+            .map(|(_, quantity)| *quantity)
+            .unwrap_or_default()
+    }
+}
+
+impl FeeAssets {
+    /// The assets left after taking the allocation, with notionals scaled down accordingly.
+    pub fn get_remaining(&self, allocation: &FeeAllocation) -> FeeAssets {
+        let get_remaining_share = |total: FPDecimal, taken: FPDecimal| {
+            if total.is_zero() {
+                FPDecimal::zero()
+            } else {
+                (total - taken) / total
+            }
+        };
+
+        FeeAssets {
+            funds: self
+                .funds
+                .iter()
+                .map(|(coin, notional)| {
+                    let taken = allocation.get_amount(&coin.denom);
+                    let remaining_share = get_remaining_share(coin.amount.into(), taken.into());
+                    (
+                        Coin {
+                            denom: coin.denom.to_owned(),
+                            amount: coin.amount - taken,
+                        },
+                        *notional * remaining_share,
+                    )
+                })
+                .collect(),
+            position_transfers: self
+                .position_transfers
+                .iter()
+                .map(|(position_transfer, notional)| {
+                    let taken = allocation.get_quantity(&position_transfer.market_id);
+                    let remaining_share = get_remaining_share(position_transfer.quantity, taken);
+                    (
+                        PositionTransferAction {
+                            quantity: position_transfer.quantity - taken,
+                            ..position_transfer.to_owned()
+                        },
+                        *notional * remaining_share,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Takes a fee given in quote notional from the assets. Coin amounts are rounded down and
+/// only assets with a positive value are used, so the value taken never exceeds the fee.
+pub fn allocate_fee(
+    fee_notional: FPDecimal,
+    fee_collection: &PerformanceFeeCollection,
+    quote_denom: &str,
+    assets: &FeeAssets,
+) -> FeeAllocation {
+    match fee_collection {
+        PerformanceFeeCollection::ProRata => allocate_fee_pro_rata(fee_notional, assets, None),
+        PerformanceFeeCollection::Quote => {
+            let quote_amount = assets
+                .funds
+                .iter()
+                .find(|(coin, _)| coin.denom == quote_denom)
+                .map(|(coin, _)| coin.amount)
+                .unwrap_or_default();
+            let quote_fee: Uint128 = fee_notional
+                .max(FPDecimal::zero())
+                .min(quote_amount.into())
+                .into();
+
+            let mut allocation = allocate_fee_pro_rata(
+                fee_notional - FPDecimal::from(quote_fee),
+                assets,
+                Some(quote_denom),
+            );
+            if !quote_fee.is_zero() {
+                allocation.funds.insert(
+                    0,
+                    Coin {
+                        denom: quote_denom.to_owned(),
+                        amount: quote_fee,
+                    },
+                );
+            }
+            allocation
+        }
+    }
+}
+
+fn allocate_fee_pro_rata(
+    fee_notional: FPDecimal,
+    assets: &FeeAssets,
+    excluded_denom: Option<&str>,
+) -> FeeAllocation {
+    let is_eligible_coin = |coin: &Coin, notional: &FPDecimal| {
+        Some(coin.denom.as_str()) != excluded_denom && *notional > FPDecimal::zero()
+    };
+
+    let eligible_notional = assets
+        .funds
+        .iter()
+        .filter(|(coin, notional)| is_eligible_coin(coin, notional))
+        .map(|(_, notional)| *notional)
+        .chain(
+            assets
+                .position_transfers
+                .iter()
+                .filter(|(_, notional)| *notional > FPDecimal::zero())
+                .map(|(_, notional)| *notional),
+        )
+        .fold(FPDecimal::zero(), |total, notional| total + notional);
+
+    let mut allocation = FeeAllocation::default();
+    if fee_notional <= FPDecimal::zero() || eligible_notional <= FPDecimal::zero() {
+        return allocation;
+    }
+
+    let fee_share = (fee_notional / eligible_notional).min(FPDecimal::one());
+
+    for (coin, notional) in assets.funds.iter() {
+        if !is_eligible_coin(coin, notional) {
+            continue;
+        }
+
+        let amount: Uint128 = (FPDecimal::from(coin.amount) * fee_share).into();
+        if !amount.is_zero() {
+            allocation.funds.push(Coin {
+                denom: coin.denom.to_owned(),
+                amount,
+            });
+        }
+    }
+
+    for (position_transfer, notional) in assets.position_transfers.iter() {
+        if *notional <= FPDecimal::zero() {
+            continue;
+        }
+
+        let quantity = position_transfer.quantity * fee_share;
+        if quantity > FPDecimal::zero() {
+            allocation
+                .position_quantities
+                .push((position_transfer.market_id.to_owned(), quantity));
+        }
+    }
+
+    allocation
+}
+
+/// Charges the fees due since the last crystallization without waiting for a redemption. The
+/// management fee is minted to the admin, and the performance fee is charged on the NAV per
+/// share gained above the fund high-water mark, or above their own mark for the lots with a
+/// higher one, paid in new shares minted to the admin. The fund mark is then raised to the NAV
+/// per share after the fee shares, so lots only pay again on redemption for what they gain
+/// above it.
+pub fn crystallize_fees(
+    mut deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
+    config: &Config,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    sync_lp_total_supply(deps.branch())?;
+    let management_fee_shares = accrue_management_fee(deps.branch(), env, config)?;
+
+    let querier = InjectiveQuerier::new(&deps.querier);
+    let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
+    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)?;
+    let fund_total_notional = get_fund_total_notional(&querier, config, &denom_decimals, None)?;
+    let nav_per_share = get_nav_per_share(fund_total_notional, lp_total_supply);
+
+    let last_crystallization_time = LAST_CRYSTALLIZATION_TIME
+        .may_load(deps.storage)?
+        .unwrap_or(env.block.time);
+    let fund_high_water_mark = FUND_HIGH_WATER_MARK.may_load(deps.storage)?;
+    let admin_owned_shares = ADMIN_OWNED_SHARES
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let lot_high_water_marks = LOT_HIGH_WATER_MARKS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|entry| entry.map(|(key, shares)| (get_high_water_mark_from_key(key), shares)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // the admin owned shares are not charged, see is_charged_performance_fees
+    let charged_shares = (lp_total_supply - admin_owned_shares).max(FPDecimal::zero());
+    let (profits, marked_value) = get_crystallization_profits(
+        nav_per_share,
+        fund_high_water_mark,
+        charged_shares,
+        &lot_high_water_marks,
+    );
+    let profits_per_year = get_profits_per_year(env, last_crystallization_time, profits);
+
+    let performance_fee_notional =
+        if should_charge_performance_fees(config, marked_value, profits_per_year) {
+            get_performance_fee(profits, config.performance_fee_rate)
+        } else {
+            FPDecimal::zero()
+        };
+
+    // mint enough shares for the admin to own the fee after dilution
+    let performance_fee_shares = if performance_fee_notional.is_zero() {
+        FPDecimal::zero()
+    } else {
+        round_lp_shares(
+            deps.storage,
+            performance_fee_notional * lp_total_supply
+                / (fund_total_notional - performance_fee_notional),
+        )?
+    };
+
+    let new_lp_total_supply = lp_total_supply + performance_fee_shares;
+    let new_nav_per_share = get_nav_per_share(fund_total_notional, new_lp_total_supply);
+
+    if !performance_fee_shares.is_zero() {
+        rebase_peak_nav_per_share(deps.storage, lp_total_supply, performance_fee_shares)?;
+        LP_TOTAL_SUPPLY.save(deps.storage, &new_lp_total_supply)?;
+        ADMIN_OWNED_SHARES.save(deps.storage, &(admin_owned_shares + performance_fee_shares))?;
+
+        let lot_id = LP_LOT_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+        LP_LOT_COUNT.save(deps.storage, &lot_id)?;
+
+        let mut admin_lp_position = LP_POSITIONS
+            .may_load(deps.storage, &config.admin)?
+            .unwrap_or_default();
+        admin_lp_position.lots.push(LPLot {
+            lot_id,
+            shares: performance_fee_shares,
+            subscription_time: env.block.time,
+            subscription_amount: performance_fee_shares * new_nav_per_share,
+            high_water_mark: new_nav_per_share,
+        });
+        LP_POSITIONS.save(deps.storage, &config.admin, &admin_lp_position)?;
+    }
+
+    // the mark is only raised once the gains above it were charged, lots with a mark below the
+    // new one are crystallized from the fund mark from now on
+    if !performance_fee_shares.is_zero() {
+        FUND_HIGH_WATER_MARK.save(deps.storage, &new_nav_per_share)?;
+
+        let crystallized_keys = LOT_HIGH_WATER_MARKS
+            .keys(
+                deps.storage,
+                None,
+                Some(Bound::inclusive(get_high_water_mark_key(new_nav_per_share))),
+                Order::Ascending,
+            )
+            .collect::<StdResult<Vec<_>>>()?;
+        for key in crystallized_keys {
+            LOT_HIGH_WATER_MARKS.remove(deps.storage, key);
+        }
+    }
+
+    let id = CRYSTALLIZATION_COUNT
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + 1;
+    CRYSTALLIZATION_COUNT.save(deps.storage, &id)?;
+    CRYSTALLIZATIONS.save(
+        deps.storage,
+        id,
+        &Crystallization {
+            id,
+            time: env.block.time,
+            nav_per_share: new_nav_per_share,
+            lp_total_supply: new_lp_total_supply,
+            management_fee_shares,
+            performance_fee_shares,
+            performance_fee_notional,
+        },
+    )?;
+    LAST_CRYSTALLIZATION_TIME.save(deps.storage, &env.block.time)?;
+
+    let lp_token_mint_messages = get_lp_token_mint_messages(
+        deps.storage,
+        env,
+        management_fee_shares + performance_fee_shares,
+        &config.admin,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(lp_token_mint_messages)
+        .add_event(
+            Event::new("crystallization")
+                .add_attribute("id", id.to_string())
+                .add_attribute("nav_per_share", new_nav_per_share.to_string())
+                .add_attribute("management_fee_shares", management_fee_shares.to_string())
+                .add_attribute("performance_fee_shares", performance_fee_shares.to_string())
+                .add_attribute(
+                    "performance_fee_notional",
+                    performance_fee_notional.to_string(),
+                ),
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cosmwasm_std::{Coin, Uint128};
+    use injective_cosmwasm::{privileged_action::PositionTransferAction, MarketId, SubaccountId};
+    use injective_math::FPDecimal;
+
+    use super::{
+        allocate_fee, allocate_fee_pro_rata, get_crystallization_profits, get_high_water_mark_key,
+        get_performance_fee, FeeAllocation, FeeAssets,
+    };
+    use crate::state::PerformanceFeeCollection;
+
+    const QUOTE_DENOM: &str = "peggy0xdAC17F958D2ee523a2206206994597C13D831ec7";
+    const MARKET_ID_A: &str = "0x4ca0f92fc28be0c9761326016b5a1a2177dd6375558365116b5bdda9abc229ce";
+    const MARKET_ID_B: &str = "0x54d4505adef6a5cef26bc403a33d595620ded4e15b9e2bc3dd489b714813366a";
+
+    fn dec(value: &str) -> FPDecimal {
+        FPDecimal::from_str(value).unwrap()
+    }
+
+    fn coin_asset(denom: &str, amount: u128, notional: &str) -> (Coin, FPDecimal) {
+        (
+            Coin {
+                denom: denom.to_owned(),
+                amount: Uint128::new(amount),
+            },
+            dec(notional),
+        )
+    }
+
+    fn position_asset(
+        market_id: &str,
+        quantity: &str,
+        notional: &str,
+    ) -> (PositionTransferAction, FPDecimal) {
+        (
+            PositionTransferAction {
+                market_id: MarketId::unchecked(market_id),
+                source_subaccount_id: SubaccountId::unchecked(
+                    "0xaf79152ac5df276d9a8e1e2e22822f9713474902000000000000000000000000",
+                ),
+                destination_subaccount_id: SubaccountId::unchecked(
+                    "0xc7dca7c15c364865f77a4fb67ab11dc95502e6fe000000000000000000000001",
+                ),
+                quantity: dec(quantity),
+            },
+            dec(notional),
+        )
+    }
+
+    /// The quote value of an allocation, priced at the notional per unit of each asset.
+    fn get_allocated_value(assets: &FeeAssets, allocation: &FeeAllocation) -> FPDecimal {
+        let funds_value = assets
+            .funds
+            .iter()
+            .filter(|(coin, _)| !coin.amount.is_zero())
+            .map(|(coin, notional)| {
+                FPDecimal::from(allocation.get_amount(&coin.denom)) * *notional
+                    / FPDecimal::from(coin.amount)
+            });
+        let positions_value = assets
+            .position_transfers
+            .iter()
+            .filter(|(position_transfer, _)| !position_transfer.quantity.is_zero())
+            .map(|(position_transfer, notional)| {
+                allocation.get_quantity(&position_transfer.market_id) * *notional
+                    / position_transfer.quantity
+            });
+
+        funds_value
+            .chain(positions_value)
+            .fold(FPDecimal::zero(), |total, value| total + value)
+    }
+
+    fn assert_fee_is_bounded(
+        profits: FPDecimal,
+        performance_fee_rate: FPDecimal,
+        fee_collection: &PerformanceFeeCollection,
+        assets: &FeeAssets,
+    ) -> FeeAllocation {
+        let fee = get_performance_fee(profits, performance_fee_rate);
+        let max_fee = performance_fee_rate * profits.max(FPDecimal::zero());
+        assert!(fee <= max_fee, "fee {} exceeds {}", fee, max_fee);
+
+        let allocation = allocate_fee(fee, fee_collection, QUOTE_DENOM, assets);
+        let allocated_value = get_allocated_value(assets, &allocation);
+        assert!(
+            allocated_value <= max_fee,
+            "allocated value {} exceeds {}",
+            allocated_value,
+            max_fee
+        );
+
+        allocation
+    }
+
+    const RANDOM_CASES: usize = 500;
+
+    /// A seeded xorshift generator, so failing random cases can be replayed.
+    struct TestRng(u64);
+
+    impl TestRng {
+        fn new(seed: u64) -> Self {
+            TestRng(seed.max(1))
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn next_below(&mut self, bound: u64) -> u64 {
+            self.next_u64() % bound
+        }
+
+        /// A decimal between zero and `max` with up to six decimals.
+        fn next_decimal(&mut self, max: u64) -> FPDecimal {
+            FPDecimal::from(self.next_below(max * 1_000_000 + 1) as u128)
+                / FPDecimal::from(1_000_000u128)
+        }
+
+        fn next_rate(&mut self) -> FPDecimal {
+            self.next_decimal(1)
+        }
+    }
+
+    fn get_random_fee_collection(rng: &mut TestRng) -> PerformanceFeeCollection {
+        if rng.next_below(2) == 0 {
+            PerformanceFeeCollection::Quote
+        } else {
+            PerformanceFeeCollection::ProRata
+        }
+    }
+
+    /// Up to four coins, the first one in quote which is its own notional, and up to three
+    /// positions of random markets, some of them worth less than nothing like the short leg of
+    /// a hedge.
+    fn get_random_assets(rng: &mut TestRng) -> FeeAssets {
+        let funds = (0..1 + rng.next_below(4))
+            .map(|index| {
+                let denom = if index == 0 {
+                    QUOTE_DENOM.to_owned()
+                } else {
+                    format!("denom{}", rng.next_u64())
+                };
+                let amount = rng.next_below(1_000_000_000_000) as u128;
+                let notional = if index == 0 {
+                    FPDecimal::from(amount)
+                } else {
+                    rng.next_decimal(1_000_000_000)
+                };
+                (
+                    Coin {
+                        denom,
+                        amount: Uint128::new(amount),
+                    },
+                    notional,
+                )
+            })
+            .collect();
+        let position_transfers = (0..rng.next_below(4))
+            .map(|_| {
+                let market_id = format!(
+                    "0x{:016x}{:016x}{:016x}{:016x}",
+                    rng.next_u64(),
+                    rng.next_u64(),
+                    rng.next_u64(),
+                    rng.next_u64()
+                );
+                let quantity = rng.next_decimal(1_000).to_string();
+                let notional = rng.next_decimal(1_000_000_000);
+                let notional = if rng.next_below(4) == 0 {
+                    FPDecimal::zero() - notional
+                } else {
+                    notional
+                };
+                position_asset(&market_id, &quantity, &notional.to_string())
+            })
+            .collect();
+
+        FeeAssets {
+            funds,
+            position_transfers,
+        }
+    }
+
+    fn get_hedged_assets() -> FeeAssets {
+        FeeAssets {
+            funds: vec![
+                coin_asset(QUOTE_DENOM, 1_000_000, "1000000"),
+                coin_asset("inj", 7_000_000_000_000_000_000, "3333.333333333333333333"),
+            ],
+            position_transfers: vec![
+                position_asset(MARKET_ID_A, "3.7", "185000"),
+                // the short leg of the hedge is worth less than nothing to the recipient
+                position_asset(MARKET_ID_B, "2.1", "-42000"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_performance_fee_is_zero_without_profits() {
+        let rate = dec("0.2");
+
+        assert_eq!(get_performance_fee(dec("-1000"), rate), FPDecimal::zero());
+        assert_eq!(
+            get_performance_fee(FPDecimal::zero(), rate),
+            FPDecimal::zero()
+        );
+        assert_eq!(get_performance_fee(dec("1000"), rate), dec("200"));
+    }
+
+    #[test]
+    fn test_performance_fee_rate_is_clamped() {
+        assert_eq!(
+            get_performance_fee(dec("1000"), dec("-0.1")),
+            FPDecimal::zero()
+        );
+        assert_eq!(get_performance_fee(dec("1000"), dec("1.5")), dec("1000"));
+    }
+
+    #[test]
+    fn test_crystallization_charges_gains_above_the_fund_mark() {
+        let (profits, marked_value) =
+            get_crystallization_profits(dec("1.5"), Some(dec("1.2")), dec("100"), &[]);
+
+        assert_eq!(profits, dec("30"));
+        assert_eq!(marked_value, dec("120"));
+    }
+
+    #[test]
+    fn test_crystallization_skips_gains_below_a_subscriber_mark() {
+        // 40 of the 100 charged shares were subscribed at 1.4, above the fund mark of 1.2
+        let (profits, marked_value) = get_crystallization_profits(
+            dec("1.5"),
+            Some(dec("1.2")),
+            dec("100"),
+            &[(dec("1.4"), dec("40"))],
+        );
+        assert_eq!(profits, dec("22"));
+        assert_eq!(marked_value, dec("128"));
+
+        // nothing is charged to the subscriber while the NAV per share is below its mark
+        let (profits, _) = get_crystallization_profits(
+            dec("1.3"),
+            Some(dec("1.2")),
+            dec("100"),
+            &[(dec("1.4"), dec("40"))],
+        );
+        assert_eq!(profits, dec("6"));
+    }
+
+    #[test]
+    fn test_crystallization_without_fund_mark_only_charges_lot_marks() {
+        let (profits, marked_value) = get_crystallization_profits(
+            dec("1.5"),
+            None,
+            dec("100"),
+            &[(dec("1"), dec("60")), (dec("1.25"), dec("30"))],
+        );
+
+        assert_eq!(profits, dec("37.5"));
+        assert_eq!(marked_value, dec("97.5"));
+    }
+
+    #[test]
+    fn test_high_water_mark_keys_keep_the_mark_order() {
+        assert!(get_high_water_mark_key(dec("1.2")) < get_high_water_mark_key(dec("1.25")));
+        assert!(get_high_water_mark_key(dec("0.99")) < get_high_water_mark_key(dec("10")));
+        assert_eq!(
+            get_high_water_mark_key(dec("1.000000000000000001")),
+            1_000_000_000_000_000_001
+        );
+    }
+
+    #[test]
+    fn test_no_fee_is_allocated_without_profits() {
+        let mut rng = TestRng::new(7);
+
+        for _ in 0..RANDOM_CASES {
+            let assets = get_random_assets(&mut rng);
+            let profits = FPDecimal::zero() - rng.next_decimal(1_000_000_000);
+            let fee_collection = get_random_fee_collection(&mut rng);
+
+            let allocation =
+                assert_fee_is_bounded(profits, rng.next_rate(), &fee_collection, &assets);
+            assert!(allocation.funds.is_empty());
+            assert!(allocation.position_quantities.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_random_fees_never_exceed_rate_times_profits() {
+        let mut rng = TestRng::new(42);
+
+        for _ in 0..RANDOM_CASES {
+            let assets = get_random_assets(&mut rng);
+            let profits = rng.next_decimal(10_000_000_000);
+            let fee_collection = get_random_fee_collection(&mut rng);
+
+            let allocation =
+                assert_fee_is_bounded(profits, rng.next_rate(), &fee_collection, &assets);
+
+            for (coin, _) in assets.funds.iter() {
+                assert!(allocation.get_amount(&coin.denom) <= coin.amount);
+            }
+            for (position_transfer, _) in assets.position_transfers.iter() {
+                assert!(
+                    allocation.get_quantity(&position_transfer.market_id)
+                        <= position_transfer.quantity
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_quote_collection_takes_quote_funds_first() {
+        let assets = get_hedged_assets();
+
+        let allocation = assert_fee_is_bounded(
+            dec("10000"),
+            dec("0.2"),
+            &PerformanceFeeCollection::Quote,
+            &assets,
+        );
+
+        assert_eq!(allocation.get_amount(QUOTE_DENOM), Uint128::new(2000));
+        assert!(allocation.get_amount("inj").is_zero());
+        assert!(allocation.position_quantities.is_empty());
+    }
+
+    #[test]
+    fn test_quote_collection_falls_back_to_pro_rata() {
+        let mut assets = get_hedged_assets();
+        assets.funds[0] = coin_asset(QUOTE_DENOM, 500, "500");
+
+        let allocation = assert_fee_is_bounded(
+            dec("100000"),
+            dec("0.2"),
+            &PerformanceFeeCollection::Quote,
+            &assets,
+        );
+
+        assert_eq!(allocation.get_amount(QUOTE_DENOM), Uint128::new(500));
+        assert!(!allocation.get_amount("inj").is_zero());
+        assert!(allocation.get_quantity(&MarketId::unchecked(MARKET_ID_A)) > FPDecimal::zero());
+    }
+
+    #[test]
+    fn test_pro_rata_collection_skips_negative_notionals() {
+        let assets = get_hedged_assets();
+
+        let allocation = assert_fee_is_bounded(
+            dec("10000"),
+            dec("0.2"),
+            &PerformanceFeeCollection::ProRata,
+            &assets,
+        );
+
+        assert!(!allocation.get_amount(QUOTE_DENOM).is_zero());
+        assert!(!allocation.get_amount("inj").is_zero());
+        assert!(allocation.get_quantity(&MarketId::unchecked(MARKET_ID_A)) > FPDecimal::zero());
+        assert!(allocation
+            .get_quantity(&MarketId::unchecked(MARKET_ID_B))
+            .is_zero());
+    }
+
+    #[test]
+    fn test_pro_rata_collection_never_takes_more_than_the_assets() {
+        let assets = get_hedged_assets();
+
+        let allocation = assert_fee_is_bounded(
+            dec("100000000"),
+            dec("0.5"),
+            &PerformanceFeeCollection::ProRata,
+            &assets,
+        );
+
+        assert_eq!(allocation.get_amount(QUOTE_DENOM), Uint128::new(1_000_000));
+        assert_eq!(
+            allocation.get_quantity(&MarketId::unchecked(MARKET_ID_A)),
+            dec("3.7")
+        );
+    }
+
+    #[test]
+    fn test_rounding_across_small_denom_amounts_rounds_down() {
+        let mut rng = TestRng::new(1337);
+
+        for _ in 0..RANDOM_CASES {
+            // amounts of a few units leave the most room for rounding up
+            let funds = (0..1 + rng.next_below(4))
+                .map(|index| {
+                    let denom = if index == 0 {
+                        QUOTE_DENOM.to_owned()
+                    } else {
+                        format!("denom{}", index)
+                    };
+                    let amount = 1 + rng.next_below(20) as u128;
+                    let notional = if index == 0 {
+                        FPDecimal::from(amount)
+                    } else {
+                        rng.next_decimal(100)
+                    };
+                    (
+                        Coin {
+                            denom,
+                            amount: Uint128::new(amount),
+                        },
+                        notional,
+                    )
+                })
+                .collect();
+            let assets = FeeAssets {
+                funds,
+                position_transfers: vec![],
+            };
+            let profits = rng.next_decimal(100);
+            let fee_collection = get_random_fee_collection(&mut rng);
+
+            assert_fee_is_bounded(profits, rng.next_rate(), &fee_collection, &assets);
+        }
+    }
+
+    #[test]
+    fn test_excluded_denom_is_not_allocated() {
+        let assets = get_hedged_assets();
+
+        let allocation = allocate_fee_pro_rata(dec("1000"), &assets, Some(QUOTE_DENOM));
+
+        assert!(allocation.get_amount(QUOTE_DENOM).is_zero());
+        assert!(!allocation.get_amount("inj").is_zero());
+        assert!(get_allocated_value(&assets, &allocation) <= dec("1000"));
+    }
+
+    #[test]
+    fn test_remaining_assets_scale_the_notionals() {
+        let assets = get_hedged_assets();
+        let allocation = allocate_fee(
+            dec("2000"),
+            &PerformanceFeeCollection::Quote,
+            QUOTE_DENOM,
+            &assets,
+        );
+
+        let remaining = assets.get_remaining(&allocation);
+
+        assert_eq!(remaining.funds[0].0.amount, Uint128::new(998_000));
+        assert_eq!(remaining.funds[0].1, dec("998000"));
+        assert_eq!(remaining.position_transfers[1].1, dec("-42000"));
+    }
+}
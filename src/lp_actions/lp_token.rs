@@ -0,0 +1,366 @@
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, QuerierWrapper, StdError,
+    StdResult, Storage, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse, TokenInfoResponse};
+use injective_cosmwasm::{
+    create_burn_tokens_msg, create_mint_tokens_msg, create_new_denom_msg, InjectiveMsgWrapper,
+    InjectiveQuerier, InjectiveQueryWrapper,
+};
+use injective_math::FPDecimal;
+
+use crate::{
+    msg::LpTokenInstantiation,
+    state::{Config, LPPosition, LpToken, CONFIG, LP_POSITIONS, LP_TOKEN, LP_TOTAL_SUPPLY},
+    ContractError,
+};
+
+pub const LP_SUBDENOM: &str = "lp";
+pub const INSTANTIATE_CW20_LP_TOKEN_REPLY_ID: u64 = 1;
+
+/// Creates or links the LP token, returning the messages that create it. A CW20 token
+/// instantiated by the fund is only stored once its address comes back in the reply.
+pub fn setup_lp_token(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
+    lp_token_instantiation: LpTokenInstantiation,
+) -> Result<Vec<SubMsg<InjectiveMsgWrapper>>, ContractError> {
+    match lp_token_instantiation {
+        LpTokenInstantiation::Tokenfactory {} => {
+            LP_TOKEN.save(
+                deps.storage,
+                &LpToken::Tokenfactory {
+                    denom: format!("factory/{}/{LP_SUBDENOM}", env.contract.address),
+                },
+            )?;
+
+            Ok(vec![SubMsg::new(create_new_denom_msg(
+                env.contract.address.to_string(),
+                LP_SUBDENOM.to_string(),
+            ))])
+        }
+        LpTokenInstantiation::InstantiateCw20 {
+            code_id,
+            name,
+            symbol,
+            decimals,
+        } => Ok(vec![SubMsg::reply_on_success(
+            WasmMsg::Instantiate {
+                admin: Some(env.contract.address.to_string()),
+                code_id,
+                msg: to_binary(&cw20_base::msg::InstantiateMsg {
+                    name: name.to_owned(),
+                    symbol,
+                    decimals,
+                    initial_balances: vec![],
+                    mint: Some(MinterResponse {
+                        minter: env.contract.address.to_string(),
+                        cap: None,
+                    }),
+                    marketing: None,
+                })?,
+                funds: vec![],
+                label: name,
+            },
+            INSTANTIATE_CW20_LP_TOKEN_REPLY_ID,
+        )]),
+        LpTokenInstantiation::LinkCw20 { address } => {
+            let address = deps.api.addr_validate(&address)?;
+            ensure_fund_is_cw20_minter(&deps.querier, env, &address)?;
+
+            // shares minted before the fund existed would have no claim the fund could honor
+            let token_info: TokenInfoResponse = deps
+                .querier
+                .query_wasm_smart(&address, &Cw20QueryMsg::TokenInfo {})?;
+            if !token_info.total_supply.is_zero() {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "The linked LP token must not have any supply",
+                )));
+            }
+
+            LP_TOKEN.save(deps.storage, &LpToken::Cw20 { address })?;
+            Ok(vec![])
+        }
+    }
+}
+
+fn ensure_fund_is_cw20_minter(
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    env: &Env,
+    address: &Addr,
+) -> Result<(), ContractError> {
+    let minter: Option<MinterResponse> =
+        querier.query_wasm_smart(address, &Cw20QueryMsg::Minter {})?;
+
+    match minter {
+        Some(minter) if minter.minter == env.contract.address && minter.cap.is_none() => Ok(()),
+        _ => Err(ContractError::Std(StdError::generic_err(
+            "The fund must be the only uncapped minter of the linked LP token",
+        ))),
+    }
+}
+
+/// LP tokens only exist in whole units, so shares are rounded down when the fund has a token.
+pub fn round_lp_shares(storage: &dyn Storage, lp_shares: FPDecimal) -> StdResult<FPDecimal> {
+    Ok(match LP_TOKEN.may_load(storage)? {
+        Some(_) => lp_shares.int(),
+        None => lp_shares,
+    })
+}
+
+/// Mints LP tokens for new shares. Shares of the admin are minted to the fund, which holds
+/// them as the admin stake so they can't leave without the minimum share check.
+pub fn get_lp_token_mint_messages(
+    storage: &dyn Storage,
+    env: &Env,
+    lp_shares: FPDecimal,
+    recipient: &Addr,
+) -> StdResult<Vec<CosmosMsg<InjectiveMsgWrapper>>> {
+    let amount: Uint128 = lp_shares.into();
+    if amount.is_zero() {
+        return Ok(vec![]);
+    }
+
+    let recipient = if recipient == &CONFIG.load(storage)?.admin {
+        &env.contract.address
+    } else {
+        recipient
+    };
+
+    Ok(match LP_TOKEN.may_load(storage)? {
+        Some(LpToken::Tokenfactory { denom }) => vec![create_mint_tokens_msg(
+            env.contract.address.to_owned(),
+            Coin { denom, amount },
+            recipient.to_string(),
+        )],
+        Some(LpToken::Cw20 { address }) => vec![WasmMsg::Execute {
+            contract_addr: address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into()],
+        None => vec![],
+    })
+}
+
+/// Burns LP tokens the fund holds, i.e. the ones sent in with a redemption.
+pub fn get_lp_token_burn_message(
+    env: &Env,
+    lp_token: &LpToken,
+    lp_shares: FPDecimal,
+) -> StdResult<CosmosMsg<InjectiveMsgWrapper>> {
+    Ok(match lp_token {
+        LpToken::Tokenfactory { denom } => create_burn_tokens_msg(
+            env.contract.address.to_owned(),
+            Coin {
+                denom: denom.to_owned(),
+                amount: lp_shares.into(),
+            },
+        ),
+        LpToken::Cw20 { address } => WasmMsg::Execute {
+            contract_addr: address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Burn {
+                amount: lp_shares.into(),
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    })
+}
+
+/// The tokenfactory LP tokens sent in with a redemption or a position transfer, which must be
+/// the only funds sent. CW20 LP tokens are sent to the fund with a hook message instead.
+pub fn get_lp_tokens_sent(lp_token: &LpToken, funds: &[Coin]) -> Result<FPDecimal, ContractError> {
+    match lp_token {
+        LpToken::Tokenfactory { denom } => match funds {
+            [coin] if &coin.denom == denom && !coin.amount.is_zero() => Ok(coin.amount.into()),
+            _ => Err(ContractError::Std(StdError::generic_err(format!(
+                "A non-zero amount of {denom} and nothing else must be sent"
+            )))),
+        },
+        LpToken::Cw20 { address } => Err(ContractError::Std(StdError::generic_err(format!(
+            "The LP tokens of {address} must be sent to the fund with a hook message"
+        )))),
+    }
+}
+
+/// The admin stake is held by the fund, so the admin redeems and transfers it without sending
+/// LP tokens.
+pub fn ensure_lp_tokens_not_sent_by_admin(
+    config: &Config,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    if sender == &config.admin {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The admin stake is held by the fund, the admin must not send LP tokens",
+        )));
+    }
+
+    Ok(())
+}
+
+/// The LP tokens sent in with the funds of a redemption or a position transfer. There are none
+/// without an LP token, or for the admin since the fund holds the admin stake.
+pub fn get_sender_lp_tokens_sent(
+    storage: &dyn Storage,
+    sender: &Addr,
+    funds: &[Coin],
+) -> Result<Option<FPDecimal>, ContractError> {
+    let Some(lp_token) = LP_TOKEN.may_load(storage)? else {
+        return Ok(None);
+    };
+
+    let config = CONFIG.load(storage)?;
+    if sender == &config.admin {
+        if !funds.is_empty() {
+            ensure_lp_tokens_not_sent_by_admin(&config, sender)?;
+        }
+        return Ok(None);
+    }
+
+    get_lp_tokens_sent(&lp_token, funds).map(Some)
+}
+
+/// Forwards LP tokens the fund holds, i.e. the ones sent in with a position transfer or the
+/// admin stake.
+pub fn get_lp_token_transfer_message(
+    lp_token: &LpToken,
+    lp_shares: FPDecimal,
+    recipient: &Addr,
+) -> StdResult<CosmosMsg<InjectiveMsgWrapper>> {
+    Ok(match lp_token {
+        LpToken::Tokenfactory { denom } => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.to_owned(),
+                amount: lp_shares.into(),
+            }],
+        }
+        .into(),
+        LpToken::Cw20 { address } => WasmMsg::Execute {
+            contract_addr: address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: lp_shares.into(),
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    })
+}
+
+pub fn query_lp_token_balance(
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    lp_token: &LpToken,
+    address: &Addr,
+) -> StdResult<FPDecimal> {
+    Ok(match lp_token {
+        LpToken::Tokenfactory { denom } => querier.query_balance(address, denom)?.amount.into(),
+        LpToken::Cw20 { address: token } => {
+            let balance: BalanceResponse = querier.query_wasm_smart(
+                token,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )?;
+            balance.balance.into()
+        }
+    })
+}
+
+pub fn query_lp_token_supply(
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    lp_token: &LpToken,
+) -> StdResult<FPDecimal> {
+    Ok(match lp_token {
+        LpToken::Tokenfactory { denom } => InjectiveQuerier::new(querier)
+            .query_token_factory_denom_total_supply(denom)?
+            .total_supply
+            .into(),
+        LpToken::Cw20 { address } => {
+            let token_info: TokenInfoResponse =
+                querier.query_wasm_smart(address, &Cw20QueryMsg::TokenInfo {})?;
+            token_info.total_supply.into()
+        }
+    })
+}
+
+/// With an LP token the token supply is the ledger, since CW20 holders can burn their tokens
+/// without going through the fund. Runs before the message mints or burns anything.
+pub fn sync_lp_total_supply(deps: DepsMut<InjectiveQueryWrapper>) -> StdResult<()> {
+    if let Some(lp_token) = LP_TOKEN.may_load(deps.storage)? {
+        LP_TOTAL_SUPPLY.save(
+            deps.storage,
+            &query_lp_token_supply(&deps.querier, &lp_token)?,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Loads the lots of an address. Lots only follow the LP token when it moves through the fund,
+/// so with an LP token the oldest lots beyond the tokens the address holds, counting the ones
+/// it sent in with the current message, are dropped: their tokens left the address and are
+/// redeemed by whoever holds them as untracked shares. The admin lots are backed by the stake
+/// the fund holds and are kept as they are.
+pub fn load_lp_position(
+    deps: Deps<InjectiveQueryWrapper>,
+    address: &Addr,
+    lp_tokens_sent: FPDecimal,
+) -> StdResult<LPPosition> {
+    let lp_position = LP_POSITIONS
+        .may_load(deps.storage, address)?
+        .unwrap_or_default();
+
+    match LP_TOKEN.may_load(deps.storage)? {
+        Some(lp_token) if address != &CONFIG.load(deps.storage)?.admin => {
+            let lp_tokens_held =
+                query_lp_token_balance(&deps.querier, &lp_token, address)? + lp_tokens_sent;
+            Ok(trim_lp_position(lp_position, lp_tokens_held))
+        }
+        _ => Ok(lp_position),
+    }
+}
+
+/// Drops the oldest lots beyond the LP tokens held.
+pub fn trim_lp_position(lp_position: LPPosition, lp_tokens_held: FPDecimal) -> LPPosition {
+    let mut shares_to_drop = (lp_position.shares() - lp_tokens_held).max(FPDecimal::zero());
+    let lots = lp_position
+        .lots
+        .into_iter()
+        .filter_map(|mut lot| {
+            let shares_from_lot = lot.shares.min(shares_to_drop);
+            shares_to_drop -= shares_from_lot;
+            if shares_from_lot == lot.shares {
+                return None;
+            }
+
+            lot.subscription_amount -= lot.subscription_amount * shares_from_lot / lot.shares;
+            lot.shares -= shares_from_lot;
+            Some(lot)
+        })
+        .collect();
+
+    LPPosition { lots }
+}
+
+/// Splits the LP tokens sent in into the shares taken from the sender's lots and the rest,
+/// which came from other holders and have no recorded cost basis or lockup.
+pub fn get_lot_and_untracked_shares(
+    lp_position: &LPPosition,
+    shares: Option<FPDecimal>,
+    lp_tokens: FPDecimal,
+) -> Result<(FPDecimal, FPDecimal), ContractError> {
+    if shares.filter(|shares| *shares != lp_tokens).is_some() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Shares must match the LP tokens sent",
+        )));
+    }
+
+    let lot_shares = lp_position.shares().min(lp_tokens);
+
+    Ok((lot_shares, lp_tokens - lot_shares))
+}
@@ -1,5 +1,8 @@
 pub mod derivative_position_helpers;
+pub mod fees;
+pub mod lp_token;
 pub mod oracle_price;
 pub mod redemptions;
 pub mod subscriptions;
+pub mod transfers;
 pub mod utils;
@@ -1,5 +1,5 @@
 use cosmwasm_std::{
-    to_binary, Addr, BankMsg, Coin, DepsMut, Env, Response, StdError, Timestamp, Uint128,
+    to_binary, Addr, Api, BankMsg, Coin, Deps, DepsMut, Env, Event, Response, StdError, Timestamp,
 };
 use injective_cosmwasm::{
     privileged_action::{PositionTransferAction, PrivilegedAction},
@@ -8,9 +8,13 @@ use injective_cosmwasm::{
 use injective_math::FPDecimal;
 
 use crate::{
+    msg::RedeemedLot,
+    nav_history::store_nav_snapshot,
+    pause::ensure_not_paused,
     state::{
-        Config, ADMIN_FEE_POSITIONS, ADMIN_OWNED_SHARES, CONFIG, DENOM_DECIMALS, IS_FUND_CLOSED,
-        LP_POSITIONS, LP_TOTAL_SUPPLY,
+        Config, EarlyRedemptionPolicy, LPLot, LPPosition, PerformanceFeeCollection,
+        ADMIN_FEE_POSITIONS, ADMIN_OWNED_SHARES, CONFIG, DENOM_DECIMALS, FUND_HIGH_WATER_MARK,
+        IS_FUND_CLOSED, LP_TOKEN, LP_TOTAL_SUPPLY,
     },
     ContractError,
 };
@@ -19,33 +23,244 @@ use super::{
     derivative_position_helpers::{
         apply_funding_to_position, get_vault_estimated_position_notional,
     },
-    utils::get_spot_base_in_quote,
+    fees::{
+        accrue_management_fee, allocate_fee, get_pending_management_fee_shares,
+        get_performance_fee, is_charged_performance_fees, save_lp_position, FeeAssets,
+    },
+    lp_token::{
+        get_lot_and_untracked_shares, get_lp_token_burn_message, get_lp_token_mint_messages,
+        load_lp_position, sync_lp_total_supply,
+    },
+    utils::{get_spot_base_in_quote, is_admin_owned_share_below_minimum},
 };
 
-const ONE_YEAR_IN_SECONDS: u64 = 365 * 24 * 60 * 60;
+pub const ONE_YEAR_IN_SECONDS: u64 = 365 * 24 * 60 * 60;
+pub const MAX_LOCKUP_PERIOD: u64 = 5 * ONE_YEAR_IN_SECONDS;
+pub const UNTRACKED_LOT_ID: u64 = 0; // LP tokens redeemed without a lot, real lot ids start at 1
+
+/// Everything a redemption would pay out, computed without touching contract state.
+pub struct RedemptionData {
+    pub lp_position: LPPosition,
+    pub redeemed_lots: Vec<RedeemedLot>,
+    pub lp_shares_to_burn: FPDecimal,
+    pub lp_total_supply: FPDecimal,
+    pub vault_quote_total_balance: FPDecimal,
+    pub assets: FeeAssets, // the redeemed share of the fund before fees and penalties
+    pub total_redemption_notional: FPDecimal,
+    pub total_profits: FPDecimal,
+    pub performance_fee_notional: FPDecimal,
+    pub early_redemption_penalty_notional: FPDecimal,
+}
+
+/// The split of a redemption between the redeemer, the admin performance fee and the
+/// early redemption penalty.
+pub struct RedemptionPayout {
+    pub redeemer_funds: Vec<Coin>,
+    pub admin_fee_funds: Vec<Coin>,
+    pub penalty_funds: Vec<Coin>,
+    pub redeemer_position_transfers: Vec<PositionTransferAction>,
+    pub admin_fee_position_quantities: Vec<(MarketId, FPDecimal)>,
+    pub penalty_position_quantities: Vec<(MarketId, FPDecimal)>, // left in the fund subaccount
+}
 
 pub fn ensure_valid_redemption(
     env: &Env,
-    subscription_time: Timestamp,
-    vault_quote_total_balance: FPDecimal,
+    config: &Config,
+    redemption: &RedemptionData,
 ) -> Result<(), ContractError> {
-    if vault_quote_total_balance < FPDecimal::zero() {
+    if redemption.vault_quote_total_balance < FPDecimal::zero() {
         return Err(ContractError::Std(StdError::generic_err(
             "Vault quote deposits are negative",
         )));
     }
 
-    if env.block.time <= subscription_time.plus_seconds(ONE_YEAR_IN_SECONDS) {
+    if redemption.lp_shares_to_burn > redemption.lp_total_supply {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Shares to redeem exceed the LP total supply",
+        )));
+    }
+
+    for redeemed_lot in redemption.redeemed_lots.iter() {
+        if !redeemed_lot.is_early_redemption
+            && env.block.time
+                < redeemed_lot
+                    .subscription_time
+                    .plus_seconds(config.lockup_period)
+        {
+            if redeemed_lot.lot_id == UNTRACKED_LOT_ID {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "LP tokens without a lot are locked up, register them to start their lockup",
+                )));
+            }
+
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Redeemer LP lot {} is still locked up",
+                redeemed_lot.lot_id
+            ))));
+        }
+    }
+
+    Ok(())
+}
+
+/// Picks the shares to burn from each lot, oldest lot first unless the LP names the lots.
+pub fn get_lots_to_redeem(
+    lp_position: &LPPosition,
+    shares: Option<FPDecimal>,
+    lot_ids: Option<Vec<u64>>,
+) -> Result<Vec<(LPLot, FPDecimal)>, ContractError> {
+    let candidate_lots = match lot_ids {
+        Some(lot_ids) => lot_ids
+            .iter()
+            .enumerate()
+            .map(|(index, lot_id)| {
+                if lot_ids[..index].contains(lot_id) {
+                    return Err(ContractError::Std(StdError::generic_err(format!(
+                        "Redeemer LP lot {lot_id} is listed more than once"
+                    ))));
+                }
+
+                lp_position
+                    .lots
+                    .iter()
+                    .find(|lot| lot.lot_id == *lot_id)
+                    .cloned()
+                    .ok_or(ContractError::Std(StdError::generic_err(format!(
+                        "Redeemer LP lot {lot_id} does not exist"
+                    ))))
+            })
+            .collect::<Result<Vec<LPLot>, ContractError>>()?,
+        None => lp_position.lots.to_owned(),
+    };
+
+    let candidate_shares = candidate_lots
+        .iter()
+        .fold(FPDecimal::zero(), |total, lot| total + lot.shares);
+    let mut shares_left_to_burn = shares.unwrap_or(candidate_shares);
+    if shares_left_to_burn <= FPDecimal::zero() || shares_left_to_burn > candidate_shares {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Shares to redeem must be greater than 0 and not exceed the redeemed lots",
+        )));
+    }
+
+    let mut lots_to_redeem = vec![];
+    for lot in candidate_lots {
+        if shares_left_to_burn.is_zero() {
+            break;
+        }
+
+        let shares_from_lot = lot.shares.min(shares_left_to_burn);
+        shares_left_to_burn -= shares_from_lot;
+        lots_to_redeem.push((lot, shares_from_lot));
+    }
+
+    Ok(lots_to_redeem)
+}
+
+pub fn get_remaining_lp_position(
+    lp_position: &LPPosition,
+    redeemed_lots: &[RedeemedLot],
+) -> LPPosition {
+    let lots = lp_position
+        .lots
+        .iter()
+        .filter_map(|lot| {
+            match redeemed_lots
+                .iter()
+                .find(|redeemed_lot| redeemed_lot.lot_id == lot.lot_id)
+            {
+                Some(redeemed_lot) if redeemed_lot.shares == lot.shares => None,
+                // the rest of the lot keeps the mark its redeemed part was charged above
+                Some(redeemed_lot) => Some(LPLot {
+                    shares: lot.shares - redeemed_lot.shares,
+                    subscription_amount: lot.subscription_amount - redeemed_lot.subscription_amount,
+                    high_water_mark: redeemed_lot.high_water_mark,
+                    ..lot.to_owned()
+                }),
+                None => Some(lot.to_owned()),
+            }
+        })
+        .collect();
+
+    LPPosition { lots }
+}
+
+pub fn ensure_valid_early_redemption_policy(
+    api: &dyn Api,
+    early_redemption_policy: &Option<EarlyRedemptionPolicy>,
+) -> Result<(), ContractError> {
+    if let Some(penalty_recipient) = early_redemption_policy
+        .as_ref()
+        .and_then(|policy| policy.penalty_recipient.as_ref())
+    {
+        api.addr_validate(penalty_recipient.as_str())?;
+    }
+
+    // without a tier for the last moments of the lockup an early redemption would be free
+    let has_no_penalty_tiers = early_redemption_policy.iter().any(|policy| {
+        !policy
+            .penalty_tiers
+            .iter()
+            .any(|tier| tier.min_remaining_lockup == 0)
+    });
+    let has_invalid_penalty_rate = early_redemption_policy.iter().any(|policy| {
+        policy.penalty_tiers.iter().any(|tier| {
+            tier.penalty_rate < FPDecimal::zero() || tier.penalty_rate >= FPDecimal::one()
+        })
+    });
+    if has_no_penalty_tiers || has_invalid_penalty_rate {
+        return Err(ContractError::InvalidEarlyRedemptionPolicy {});
+    }
+
+    Ok(())
+}
+
+pub fn ensure_admin_keeps_minimum_share(
+    sender: &Addr,
+    config: &Config,
+    is_fund_closed: bool,
+    admin_owned_shares: FPDecimal,
+    lp_shares_to_burn: FPDecimal,
+    new_lp_total_supply: FPDecimal,
+) -> Result<(), ContractError> {
+    if sender != &config.admin || is_fund_closed {
+        return Ok(());
+    }
+
+    if is_admin_owned_share_below_minimum(
+        admin_owned_shares - lp_shares_to_burn,
+        new_lp_total_supply,
+    ) {
         return Err(ContractError::Std(StdError::generic_err(
-            "Redeemer LP position has not been updated",
+            "Admin must own at least 10% of fund",
         )));
     }
 
     Ok(())
 }
 
+pub fn get_profits_per_year(
+    env: &Env,
+    subscription_time: Timestamp,
+    total_profits: FPDecimal,
+) -> FPDecimal {
+    // a position queried in its subscription block has no elapsed time to annualize over
+    let time_since_subscription = (env.block.time.seconds() - subscription_time.seconds()).max(1);
+
+    total_profits * FPDecimal::from(ONE_YEAR_IN_SECONDS as u128)
+        / FPDecimal::from(time_since_subscription as u128)
+}
+
+pub fn should_charge_performance_fees(
+    config: &Config,
+    subscription_amount: FPDecimal,
+    profits_per_year: FPDecimal,
+) -> bool {
+    profits_per_year > subscription_amount * config.min_yearly_roi_for_fees
+}
+
 pub fn get_updated_redemption_notional_and_update_derivative_position_transfers(
-    mut total_redemption_notional: FPDecimal,
+    total_redemption_notional: FPDecimal,
     position_transfers: &mut Vec<PositionTransferAction>,
     querier: &InjectiveQuerier,
     market_id: &MarketId,
@@ -80,138 +295,80 @@ pub fn get_updated_redemption_notional_and_update_derivative_position_transfers(
             }),
             &derivative_market_res,
         );
-        total_redemption_notional += position_notional;
+        return Ok(total_redemption_notional + position_notional);
     };
 
     Ok(total_redemption_notional)
 }
 
-pub fn get_redemption_response(
-    deps: DepsMut<InjectiveQueryWrapper>,
-    sender: &Addr,
-    config: &Config,
-    funds_to_return: &[Coin],
-    total_profits: FPDecimal,
-    total_redemption_notional: FPDecimal,
-    position_transfers: Vec<PositionTransferAction>,
-    should_charge_performance_fees: bool,
-) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
-    let mut response = Response::new();
-
-    let mut admin_fee_positions = ADMIN_FEE_POSITIONS
-        .may_load(deps.storage)?
-        .unwrap_or_default();
-
-    let performance_fee = total_profits * config.performance_fee_rate / total_redemption_notional;
-
-    for (_, coin) in funds_to_return.iter().enumerate() {
-        let admin_fee: Uint128 = if should_charge_performance_fees {
-            (performance_fee * coin.amount.into()).into()
-        } else {
-            Uint128::zero()
-        };
-
-        if admin_fee > Uint128::zero() {
-            let admin_send_message = BankMsg::Send {
-                to_address: config.admin.to_string(),
-                amount: vec![Coin {
-                    denom: coin.denom.to_owned(),
-                    amount: admin_fee,
-                }],
-            };
-            response = response.add_message(admin_send_message);
-        }
-
-        let redeemer_send_message = BankMsg::Send {
-            to_address: sender.to_string(),
-            amount: vec![Coin {
-                denom: coin.denom.to_owned(),
-                amount: coin.amount - admin_fee,
-            }],
-        };
-        response = response.add_message(redeemer_send_message);
-    }
-
-    for (_, position_transfer) in position_transfers.iter().enumerate() {
-        let admin_fee_position_quantity: FPDecimal = if should_charge_performance_fees {
-            performance_fee * position_transfer.quantity
-        } else {
-            FPDecimal::zero()
-        };
-
-        if admin_fee_position_quantity > FPDecimal::zero() {
-            let existing_admin_fee_position =
-                admin_fee_positions.get(&position_transfer.market_id.to_owned());
-            let new_admin_fee_position_quantity = match existing_admin_fee_position {
-                Some(q) => *q + admin_fee_position_quantity,
-                None => admin_fee_position_quantity,
-            };
-            admin_fee_positions.insert(
-                position_transfer.market_id.to_owned(),
-                new_admin_fee_position_quantity,
-            );
-        }
-
-        let redeemer_privileged_action = PrivilegedAction {
-            synthetic_trade: None,
-            position_transfer: Some(PositionTransferAction {
-                market_id: position_transfer.market_id.to_owned(),
-                source_subaccount_id: position_transfer.source_subaccount_id.to_owned(),
-                destination_subaccount_id: position_transfer.destination_subaccount_id.to_owned(),
-                quantity: position_transfer.quantity - admin_fee_position_quantity,
-            }),
-        };
-
-        response = response.set_data(to_binary(&Some(redeemer_privileged_action))?);
-    }
-
-    if should_charge_performance_fees {
-        ADMIN_FEE_POSITIONS.save(deps.storage, &admin_fee_positions)?;
-    }
-
-    Ok(response)
-}
-
-pub fn get_fund_redemption_response(
-    deps: DepsMut<InjectiveQueryWrapper>,
+pub fn get_redemption_data(
+    deps: Deps<InjectiveQueryWrapper>,
     env: &Env,
+    config: &Config,
     sender: &Addr,
+    lp_position: LPPosition,
     redeemer_subaccount_id: SubaccountId,
-) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    shares: Option<FPDecimal>,
+    lot_ids: Option<Vec<u64>>,
+    allow_early_redemption: bool,
+    untracked_shares: FPDecimal,
+) -> Result<RedemptionData, ContractError> {
     let querier = InjectiveQuerier::new(&deps.querier);
-    let config = CONFIG.load(deps.storage)?;
 
     let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
-    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)?;
-    let mut lp_positions = LP_POSITIONS.load(deps.storage)?;
-
-    let mut admin_owned_shares = ADMIN_OWNED_SHARES.load(deps.storage)?;
-    let is_fund_closed = IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default();
-
+    let lp_total_supply =
+        LP_TOTAL_SUPPLY.load(deps.storage)? + get_pending_management_fee_shares(deps, env, config)?;
     let quote_decimals = denom_decimals.get(&config.quote_denom).unwrap();
 
     let deposit_quote_res =
         querier.query_subaccount_deposit(&config.fund_subaccount_id, &config.quote_denom)?;
     let vault_quote_total_balance = deposit_quote_res.deposits.total_balance;
 
-    let lp_position = lp_positions
-        .get(sender)
-        .ok_or(ContractError::Std(StdError::generic_err(
+    if lp_position.lots.is_empty() && untracked_shares.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
             "Redeemer LP position does not exist",
-        )))?;
-    let lp_shares_to_burn = lp_position.shares;
+        )));
+    }
 
-    ensure_valid_redemption(
-        env,
-        lp_position.subscription_time,
-        vault_quote_total_balance,
-    )?;
+    let fund_high_water_mark = FUND_HIGH_WATER_MARK
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let is_only_untracked = shares == Some(FPDecimal::zero()) && !untracked_shares.is_zero();
+    let mut lots_to_redeem = if is_only_untracked {
+        vec![]
+    } else {
+        get_lots_to_redeem(&lp_position, shares, lot_ids)?
+    };
+    if !untracked_shares.is_zero() {
+        // shares received outside the fund and not registered are priced at the worst case:
+        // bought now at the fund high-water mark, so they serve the full lockup and pay the
+        // performance fee, see get_lp_token_registration_response
+        lots_to_redeem.push((
+            LPLot {
+                lot_id: UNTRACKED_LOT_ID,
+                shares: untracked_shares,
+                subscription_time: env.block.time,
+                subscription_amount: untracked_shares * fund_high_water_mark,
+                high_water_mark: fund_high_water_mark,
+            },
+            untracked_shares,
+        ));
+    }
+    let lp_shares_to_burn = lots_to_redeem
+        .iter()
+        .fold(FPDecimal::zero(), |total, (_, shares_from_lot)| {
+            total + *shares_from_lot
+        });
 
     let quote_withdrawal_amount = vault_quote_total_balance * lp_shares_to_burn / lp_total_supply;
-    let mut funds_to_return = vec![Coin {
-        denom: config.quote_denom.to_owned(),
-        amount: quote_withdrawal_amount.into(),
-    }];
+    let mut funds_to_return = vec![(
+        Coin {
+            denom: config.quote_denom.to_owned(),
+            amount: quote_withdrawal_amount.into(),
+        },
+        quote_withdrawal_amount,
+    )];
 
     let mut total_redemption_notional = quote_withdrawal_amount;
 
@@ -220,24 +377,36 @@ pub fn get_fund_redemption_response(
             .spot_oracle_types
             .get(index)
             .expect("oracle type should exist");
-        total_redemption_notional += get_spot_base_in_quote(
+        let mut spot_funds_to_return = vec![];
+        let spot_notional = get_spot_base_in_quote(
             &querier,
             &config.fund_subaccount_id.to_owned(),
             &market_id.to_owned(),
             &denom_decimals,
             quote_decimals,
             oracle_type,
-            Some((lp_shares_to_burn, lp_total_supply, &mut funds_to_return)),
+            Some((
+                lp_shares_to_burn,
+                lp_total_supply,
+                &mut spot_funds_to_return,
+            )),
         )?;
+        total_redemption_notional += spot_notional;
+        funds_to_return.extend(
+            spot_funds_to_return
+                .into_iter()
+                .map(|coin| (coin, spot_notional)),
+        );
     }
 
     let mut position_transfers = vec![];
 
-    for (_, market_id) in config.derivative_market_ids.iter().enumerate() {
-        total_redemption_notional +=
+    for market_id in config.derivative_market_ids.iter() {
+        let mut market_position_transfers = vec![];
+        let updated_redemption_notional =
             get_updated_redemption_notional_and_update_derivative_position_transfers(
                 total_redemption_notional,
-                &mut position_transfers,
+                &mut market_position_transfers,
                 &querier,
                 market_id,
                 config.fund_subaccount_id.to_owned(),
@@ -245,42 +414,324 @@ pub fn get_fund_redemption_response(
                 lp_shares_to_burn,
                 lp_total_supply,
             )?;
+        position_transfers.extend(
+            market_position_transfers
+                .into_iter()
+                .map(|position_transfer| {
+                    (
+                        position_transfer,
+                        updated_redemption_notional - total_redemption_notional,
+                    )
+                }),
+        );
+        total_redemption_notional = updated_redemption_notional;
     }
 
-    let total_profits = total_redemption_notional - lp_position.subscription_amount;
-    let time_since_redemption = env.block.time.seconds() - lp_position.subscription_time.seconds();
-    let profits_per_year = total_profits * FPDecimal::from(ONE_YEAR_IN_SECONDS as u128)
-        / FPDecimal::from(time_since_redemption as u128);
+    let mut redeemed_lots = vec![];
+    let mut total_profits = FPDecimal::zero();
+    let mut performance_fee_notional = FPDecimal::zero();
+    let mut early_redemption_penalty_notional = FPDecimal::zero();
+
+    for (lot, shares_from_lot) in lots_to_redeem {
+        let subscription_amount = lot.subscription_amount * shares_from_lot / lot.shares;
+        let redemption_notional = total_redemption_notional * shares_from_lot / lp_shares_to_burn;
+
+        let lockup_end = lot.subscription_time.plus_seconds(config.lockup_period);
+        let early_redemption_policy = config
+            .early_redemption_policy
+            .as_ref()
+            .filter(|_| allow_early_redemption && env.block.time < lockup_end);
+        let is_early_redemption = early_redemption_policy.is_some();
+        let early_redemption_penalty = match early_redemption_policy {
+            Some(policy) => {
+                let remaining_lockup = lockup_end.seconds() - env.block.time.seconds();
+                redemption_notional * policy.get_penalty_rate(remaining_lockup)
+            }
+            None => FPDecimal::zero(),
+        };
 
-    let should_charge_performance_fees =
-        profits_per_year > lp_position.subscription_amount * config.min_yearly_roi_for_fees;
+        let net_redemption_notional = redemption_notional - early_redemption_penalty;
+        let profits = net_redemption_notional - subscription_amount;
+        let profits_per_year = get_profits_per_year(env, lot.subscription_time, profits);
 
-    let new_lp_total_supply = lp_total_supply - lp_shares_to_burn;
-    LP_TOTAL_SUPPLY.save(deps.storage, &new_lp_total_supply)?;
-    lp_positions.remove(sender);
-    LP_POSITIONS.save(deps.storage, &lp_positions)?;
+        // gains that only recover an earlier drawdown from the peak are not charged again
+        let profits_above_high_water_mark = lot.get_profits_above_high_water_mark(
+            fund_high_water_mark,
+            shares_from_lot,
+            profits,
+            net_redemption_notional,
+        );
 
-    if sender == &config.admin && !is_fund_closed {
-        admin_owned_shares -= lp_shares_to_burn;
+        let should_charge_performance_fees = is_charged_performance_fees(config, sender)
+            && should_charge_performance_fees(config, subscription_amount, profits_per_year);
+        let performance_fee = if should_charge_performance_fees {
+            get_performance_fee(profits_above_high_water_mark, config.performance_fee_rate)
+        } else {
+            FPDecimal::zero()
+        };
 
-        if admin_owned_shares * FPDecimal::from(10u128) < new_lp_total_supply {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Admin must own at least 10% of fund",
-            )));
+        total_profits += profits;
+        performance_fee_notional += performance_fee;
+        early_redemption_penalty_notional += early_redemption_penalty;
+
+        redeemed_lots.push(RedeemedLot {
+            lot_id: lot.lot_id,
+            shares: shares_from_lot,
+            subscription_time: lot.subscription_time,
+            subscription_amount,
+            redemption_notional,
+            profits,
+            high_water_mark: lot.get_high_water_mark(fund_high_water_mark),
+            profits_above_high_water_mark,
+            performance_fee,
+            is_early_redemption,
+            early_redemption_penalty,
+        });
+    }
+
+    Ok(RedemptionData {
+        lp_position,
+        redeemed_lots,
+        lp_shares_to_burn,
+        lp_total_supply,
+        vault_quote_total_balance,
+        assets: FeeAssets {
+            funds: funds_to_return,
+            position_transfers,
+        },
+        total_redemption_notional,
+        total_profits,
+        performance_fee_notional,
+        early_redemption_penalty_notional,
+    })
+}
+
+pub fn get_redemption_payout(config: &Config, redemption: &RedemptionData) -> RedemptionPayout {
+    // the penalty is taken first so the performance fee only applies to what is left of it
+    let penalty = allocate_fee(
+        redemption.early_redemption_penalty_notional,
+        &PerformanceFeeCollection::ProRata,
+        &config.quote_denom,
+        &redemption.assets,
+    );
+    let assets_after_penalty = redemption.assets.get_remaining(&penalty);
+
+    let admin_fee = allocate_fee(
+        redemption.performance_fee_notional,
+        &config.performance_fee_collection,
+        &config.quote_denom,
+        &assets_after_penalty,
+    );
+    let redeemer_assets = assets_after_penalty.get_remaining(&admin_fee);
+
+    RedemptionPayout {
+        redeemer_funds: redeemer_assets
+            .funds
+            .into_iter()
+            .map(|(coin, _)| coin)
+            .collect(),
+        admin_fee_funds: admin_fee.funds,
+        penalty_funds: penalty.funds,
+        redeemer_position_transfers: redeemer_assets
+            .position_transfers
+            .into_iter()
+            .map(|(position_transfer, _)| position_transfer)
+            .collect(),
+        admin_fee_position_quantities: admin_fee.position_quantities,
+        penalty_position_quantities: penalty.position_quantities,
+    }
+}
+
+pub fn get_redemption_response(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    mut response: Response<InjectiveMsgWrapper>,
+    sender: &Addr,
+    config: &Config,
+    payout: RedemptionPayout,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    for coin in payout.admin_fee_funds {
+        let admin_send_message = BankMsg::Send {
+            to_address: config.admin.to_string(),
+            amount: vec![coin],
+        };
+        response = response.add_message(admin_send_message);
+    }
+
+    // without a penalty recipient the penalty coins are simply not sent out of the fund
+    if let Some(penalty_recipient) = config
+        .early_redemption_policy
+        .as_ref()
+        .and_then(|policy| policy.penalty_recipient.as_ref())
+    {
+        for coin in payout.penalty_funds {
+            let penalty_send_message = BankMsg::Send {
+                to_address: penalty_recipient.to_string(),
+                amount: vec![coin],
+            };
+            response = response.add_message(penalty_send_message);
         }
+    }
 
-        ADMIN_OWNED_SHARES.save(deps.storage, &admin_owned_shares)?;
-        return Ok(Response::new());
+    for coin in payout.redeemer_funds {
+        let redeemer_send_message = BankMsg::Send {
+            to_address: sender.to_string(),
+            amount: vec![coin],
+        };
+        response = response.add_message(redeemer_send_message);
     }
 
-    get_redemption_response(
-        deps,
+    for (market_id, quantity) in payout.admin_fee_position_quantities {
+        let admin_fee_position = ADMIN_FEE_POSITIONS
+            .may_load(deps.storage, market_id.as_str())?
+            .unwrap_or_default();
+        ADMIN_FEE_POSITIONS.save(
+            deps.storage,
+            market_id.as_str(),
+            &(admin_fee_position + quantity),
+        )?;
+    }
+
+    // the position part of the penalty is never transferred, so it stays with the fund
+    for (market_id, quantity) in payout.penalty_position_quantities {
+        response = response.add_event(
+            Event::new("early_redemption_penalty_position")
+                .add_attribute("market_id", market_id.as_str())
+                .add_attribute("quantity", quantity.to_string())
+                .add_attribute("kept_by_fund", "true"),
+        );
+    }
+
+    for position_transfer in payout.redeemer_position_transfers {
+        let redeemer_privileged_action = PrivilegedAction {
+            synthetic_trade: None,
+            position_transfer: Some(position_transfer),
+        };
+
+        response = response.set_data(to_binary(&Some(redeemer_privileged_action))?);
+    }
+
+    Ok(response)
+}
+
+pub fn get_fund_redemption_response(
+    mut deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
+    sender: &Addr,
+    redeemer_subaccount_id: SubaccountId,
+    shares: Option<FPDecimal>,
+    lot_ids: Option<Vec<u64>>,
+    allow_early_redemption: bool,
+    lp_tokens_sent: Option<FPDecimal>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(deps.storage, true)?;
+
+    sync_lp_total_supply(deps.branch())?;
+    let management_fee_shares = accrue_management_fee(deps.branch(), env, &config)?;
+    store_nav_snapshot(deps.branch(), env, &config)?;
+
+    let lp_position = load_lp_position(deps.as_ref(), sender, lp_tokens_sent.unwrap_or_default())?;
+    let lp_token = LP_TOKEN.may_load(deps.storage)?;
+    let (shares, untracked_shares) = match (lp_token.as_ref(), lp_tokens_sent) {
+        (Some(_), Some(lp_tokens_sent)) => {
+            let (lot_shares, untracked_shares) =
+                get_lot_and_untracked_shares(&lp_position, shares, lp_tokens_sent)?;
+            (Some(lot_shares), untracked_shares)
+        }
+        // the admin stake is held by the fund
+        (Some(_), None) if sender == &config.admin => (shares, FPDecimal::zero()),
+        (None, None) => (shares, FPDecimal::zero()),
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "LP tokens must be sent if and only if the fund has an LP token",
+            )))
+        }
+    };
+
+    let mut admin_owned_shares = ADMIN_OWNED_SHARES
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let is_fund_closed = IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default();
+
+    let redemption = get_redemption_data(
+        deps.as_ref(),
+        env,
+        &config,
+        sender,
+        lp_position,
+        redeemer_subaccount_id,
+        shares,
+        lot_ids,
+        allow_early_redemption,
+        untracked_shares,
+    )?;
+
+    ensure_valid_redemption(env, &config, &redemption)?;
+
+    let new_lp_total_supply = redemption.lp_total_supply - redemption.lp_shares_to_burn;
+    ensure_admin_keeps_minimum_share(
         sender,
         &config,
-        &funds_to_return,
-        total_profits,
-        total_redemption_notional,
-        position_transfers,
-        should_charge_performance_fees,
-    )
+        is_fund_closed,
+        admin_owned_shares,
+        redemption.lp_shares_to_burn,
+        new_lp_total_supply,
+    )?;
+
+    LP_TOTAL_SUPPLY.save(deps.storage, &new_lp_total_supply)?;
+    let remaining_lp_position =
+        get_remaining_lp_position(&redemption.lp_position, &redemption.redeemed_lots);
+    save_lp_position(deps.storage, &config, sender, &remaining_lp_position)?;
+
+    if sender == &config.admin {
+        admin_owned_shares -= redemption.lp_shares_to_burn;
+        ADMIN_OWNED_SHARES.save(deps.storage, &admin_owned_shares)?;
+    }
+
+    let mut response = Response::new().add_messages(get_lp_token_mint_messages(
+        deps.storage,
+        env,
+        management_fee_shares,
+        &config.admin,
+    )?);
+    if let Some(lp_token) = lp_token.as_ref() {
+        response = response.add_message(get_lp_token_burn_message(
+            env,
+            lp_token,
+            redemption.lp_shares_to_burn,
+        )?);
+    }
+
+    let payout = get_redemption_payout(&config, &redemption);
+    let response = get_redemption_response(deps, response, sender, &config, payout)?;
+
+    Ok(response
+        .add_attribute(
+            "early_redemption_penalty",
+            redemption.early_redemption_penalty_notional.to_string(),
+        )
+        .add_events(redemption.redeemed_lots.iter().map(get_redeemed_lot_event)))
+}
+
+fn get_redeemed_lot_event(redeemed_lot: &RedeemedLot) -> Event {
+    Event::new("redeemed_lot")
+        .add_attribute("lot_id", redeemed_lot.lot_id.to_string())
+        .add_attribute("shares", redeemed_lot.shares.to_string())
+        .add_attribute(
+            "subscription_amount",
+            redeemed_lot.subscription_amount.to_string(),
+        )
+        .add_attribute(
+            "redemption_notional",
+            redeemed_lot.redemption_notional.to_string(),
+        )
+        .add_attribute("performance_fee", redeemed_lot.performance_fee.to_string())
+        .add_attribute(
+            "early_redemption_penalty",
+            redeemed_lot.early_redemption_penalty.to_string(),
+        )
+        .add_attribute(
+            "realized_pnl",
+            (redeemed_lot.profits - redeemed_lot.performance_fee).to_string(),
+        )
 }
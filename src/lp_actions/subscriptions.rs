@@ -1,34 +1,45 @@
 use cosmwasm_std::{Addr, Coin, DepsMut, Env, Response, StdError, StdResult};
-use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQuerier, InjectiveQueryWrapper};
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
 use injective_math::FPDecimal;
 
 use crate::{
+    nav_history::store_nav_snapshot,
+    pause::ensure_not_paused,
     state::{
-        LPPosition, ADMIN_OWNED_SHARES, CONFIG, DENOM_DECIMALS, IS_FUND_CLOSED, LP_POSITIONS,
-        LP_TOTAL_SUPPLY,
+        Config, LPLot, ADMIN_OWNED_SHARES, CONFIG, FUND_HIGH_WATER_MARK, IS_FUND_CLOSED,
+        LOT_HIGH_WATER_MARKS, LP_LOT_COUNT, LP_POSITIONS, LP_TOTAL_SUPPLY,
     },
     ContractError,
 };
 
-use super::utils::get_fund_total_notional;
+use super::{
+    fees::{accrue_management_fee, save_lp_position},
+    lp_token::{get_lp_token_mint_messages, round_lp_shares, sync_lp_total_supply},
+    utils::is_admin_owned_share_below_minimum,
+};
 
 pub fn get_fund_subscription_response(
-    deps: DepsMut<InjectiveQueryWrapper>,
+    mut deps: DepsMut<InjectiveQueryWrapper>,
     env: &Env,
     sender: &Addr,
     total_funds_supplied: Vec<Coin>,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
-    let querier = InjectiveQuerier::new(&deps.querier);
     let config = CONFIG.load(deps.storage)?;
 
-    let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
-    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)?;
-
     let is_fund_closed = IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default();
     if is_fund_closed {
         return Err(ContractError::Std(StdError::generic_err("Fund is closed")));
     }
 
+    ensure_not_paused(deps.storage, false)?;
+
+    sync_lp_total_supply(deps.branch())?;
+    let management_fee_shares = accrue_management_fee(deps.branch(), env, &config)?;
+
+    // the subscription is priced at the snapshot NAV
+    let nav_snapshot = store_nav_snapshot(deps.branch(), env, &config)?;
+    let lp_total_supply = nav_snapshot.lp_total_supply;
+
     let mut has_invalid_coins = false;
     let mut total_quote_funds_supplied: u128 = 0;
 
@@ -45,12 +56,28 @@ pub fn get_fund_subscription_response(
         )));
     }
 
-    let fund_total_notional = get_fund_total_notional(&querier, &config, &denom_decimals)?;
-    let lp_shares_to_mint = get_token_mint_data(
-        total_quote_funds_supplied.into(),
-        fund_total_notional,
-        lp_total_supply,
+    let lp_shares_to_mint = round_lp_shares(
+        deps.storage,
+        get_token_mint_data(
+            total_quote_funds_supplied.into(),
+            nav_snapshot.nav,
+            lp_total_supply,
+        )?,
     )?;
+    if lp_shares_to_mint.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Insufficient funds to mint LP tokens",
+        )));
+    }
+
+    let mut lp_token_mint_messages =
+        get_lp_token_mint_messages(deps.storage, env, management_fee_shares, &config.admin)?;
+    lp_token_mint_messages.extend(get_lp_token_mint_messages(
+        deps.storage,
+        env,
+        lp_shares_to_mint,
+        sender,
+    )?);
 
     store_subscription(
         deps,
@@ -59,10 +86,10 @@ pub fn get_fund_subscription_response(
         lp_shares_to_mint,
         lp_total_supply,
         total_quote_funds_supplied.into(),
-        &config.admin,
+        &config,
     )?;
 
-    Ok(Response::new())
+    Ok(Response::new().add_messages(lp_token_mint_messages))
 }
 
 pub fn store_subscription(
@@ -72,38 +99,46 @@ pub fn store_subscription(
     lp_shares_to_mint: FPDecimal,
     lp_total_supply: FPDecimal,
     total_quote_funds_supplied: FPDecimal,
-    admin: &Addr,
+    config: &Config,
 ) -> StdResult<()> {
-    let mut lp_positions = LP_POSITIONS.may_load(deps.storage)?.unwrap_or_default();
-    let mut admin_owned_shares = ADMIN_OWNED_SHARES.load(deps.storage)?;
+    let mut admin_owned_shares = ADMIN_OWNED_SHARES
+        .may_load(deps.storage)?
+        .unwrap_or_default();
 
     let new_lp_total_supply = lp_total_supply + lp_shares_to_mint;
 
-    let old_lp_position = lp_positions.insert(
-        sender.to_owned(),
-        LPPosition {
-            shares: lp_shares_to_mint,
-            subscription_time: env.block.time,
-            subscription_amount: total_quote_funds_supplied,
-        },
-    );
-    if old_lp_position.is_some() {
-        // consider allowing multiple subscriptions from same address through calculating average profits
-        return Err(StdError::generic_err("Already subscribed"));
+    let lot_id = LP_LOT_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    LP_LOT_COUNT.save(deps.storage, &lot_id)?;
+
+    let high_water_mark = total_quote_funds_supplied / lp_shares_to_mint;
+
+    // the first subscription, or the first one after the fund was emptied, sets the fund mark
+    if lp_total_supply.is_zero() {
+        FUND_HIGH_WATER_MARK.save(deps.storage, &high_water_mark)?;
+        LOT_HIGH_WATER_MARKS.clear(deps.storage);
     }
 
-    LP_POSITIONS.save(deps.storage, &lp_positions)?;
+    let mut lp_position = LP_POSITIONS
+        .may_load(deps.storage, sender)?
+        .unwrap_or_default();
+    lp_position.lots.push(LPLot {
+        lot_id,
+        shares: lp_shares_to_mint,
+        subscription_time: env.block.time,
+        subscription_amount: total_quote_funds_supplied,
+        high_water_mark,
+    });
+
+    save_lp_position(deps.storage, config, sender, &lp_position)?;
     LP_TOTAL_SUPPLY.save(deps.storage, &new_lp_total_supply)?;
 
-    let is_subscriber_the_admin = sender == admin;
+    let is_subscriber_the_admin = sender == &config.admin;
     if is_subscriber_the_admin {
         admin_owned_shares += lp_shares_to_mint;
         ADMIN_OWNED_SHARES.save(deps.storage, &admin_owned_shares)?;
     }
 
-    let is_admin_owned_shares_below_10_percent =
-        admin_owned_shares * FPDecimal::from(10u128) < new_lp_total_supply;
-    if is_admin_owned_shares_below_10_percent {
+    if is_admin_owned_share_below_minimum(admin_owned_shares, new_lp_total_supply) {
         return Err(StdError::generic_err("Admin must own at least 10% of fund"));
     }
 
@@ -0,0 +1,242 @@
+use cosmwasm_std::{Addr, DepsMut, Env, Event, Response, StdError, Storage};
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
+use injective_math::FPDecimal;
+
+use crate::{
+    state::{
+        LPLot, LPPosition, ADMIN_OWNED_SHARES, CONFIG, FUND_HIGH_WATER_MARK, IS_FUND_CLOSED,
+        LP_LOT_COUNT, LP_POSITIONS, LP_TOKEN, LP_TOTAL_SUPPLY,
+    },
+    ContractError,
+};
+
+use super::{
+    fees::save_lp_position,
+    lp_token::{
+        get_lp_token_transfer_message, load_lp_position, query_lp_token_balance,
+        sync_lp_total_supply,
+    },
+    redemptions::ensure_admin_keeps_minimum_share,
+};
+
+/// Splits the transferred shares off the sender lots, oldest lot first. Lots transferred in
+/// full keep their id, a partially transferred lot is split into a new lot for the recipient
+/// with its cost basis taken pro-rata.
+pub fn split_lots_to_transfer(
+    storage: &mut dyn Storage,
+    lp_position: &LPPosition,
+    shares: FPDecimal,
+) -> Result<(LPPosition, Vec<LPLot>), ContractError> {
+    if shares <= FPDecimal::zero() || shares > lp_position.shares() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Shares to transfer must be greater than 0 and not exceed the sender position",
+        )));
+    }
+
+    let mut shares_left_to_transfer = shares;
+    let mut remaining_lots = vec![];
+    let mut transferred_lots = vec![];
+
+    for lot in lp_position.lots.iter() {
+        let shares_from_lot = lot.shares.min(shares_left_to_transfer);
+        shares_left_to_transfer -= shares_from_lot;
+
+        if shares_from_lot.is_zero() {
+            remaining_lots.push(lot.to_owned());
+        } else if shares_from_lot == lot.shares {
+            transferred_lots.push(lot.to_owned());
+        } else {
+            let subscription_amount = lot.subscription_amount * shares_from_lot / lot.shares;
+
+            let lot_id = LP_LOT_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+            LP_LOT_COUNT.save(storage, &lot_id)?;
+
+            transferred_lots.push(LPLot {
+                lot_id,
+                shares: shares_from_lot,
+                subscription_amount,
+                ..lot.to_owned()
+            });
+            remaining_lots.push(LPLot {
+                shares: lot.shares - shares_from_lot,
+                subscription_amount: lot.subscription_amount - subscription_amount,
+                ..lot.to_owned()
+            });
+        }
+    }
+
+    Ok((
+        LPPosition {
+            lots: remaining_lots,
+        },
+        transferred_lots,
+    ))
+}
+
+/// Moves the lots of the transferred shares to the recipient. With an LP token the tokens are
+/// sent in and forwarded to the recipient, so the lots follow them; tokens beyond the sender's
+/// lots are forwarded without a lot.
+pub fn get_fund_transfer_response(
+    mut deps: DepsMut<InjectiveQueryWrapper>,
+    sender: &Addr,
+    recipient: &Addr,
+    shares: FPDecimal,
+    lp_tokens_sent: Option<FPDecimal>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if sender == recipient {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Cannot transfer a position to the same address",
+        )));
+    }
+
+    let lp_token = LP_TOKEN.may_load(deps.storage)?;
+    match (lp_token.as_ref(), lp_tokens_sent) {
+        (Some(_), Some(lp_tokens_sent)) if lp_tokens_sent != shares => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Shares must match the LP tokens sent",
+            )))
+        }
+        // the admin stake is held by the fund
+        (Some(_), None) if sender == &config.admin => {}
+        (Some(_), Some(_)) | (None, None) => {}
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "LP tokens must be sent if and only if the fund has an LP token",
+            )))
+        }
+    }
+
+    sync_lp_total_supply(deps.branch())?;
+    let mut admin_owned_shares = ADMIN_OWNED_SHARES
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let lp_position = load_lp_position(deps.as_ref(), sender, lp_tokens_sent.unwrap_or_default())?;
+    if lp_tokens_sent.is_none() && lp_position.lots.is_empty() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Sender does not have an LP position",
+        )));
+    }
+
+    let lot_shares = match lp_tokens_sent {
+        Some(_) => lp_position.shares().min(shares),
+        None => shares,
+    };
+    let (remaining_lp_position, transferred_lots) = if lot_shares.is_zero() {
+        (lp_position, vec![])
+    } else {
+        split_lots_to_transfer(deps.storage, &lp_position, lot_shares)?
+    };
+
+    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)?;
+    let is_fund_closed = IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default();
+
+    ensure_admin_keeps_minimum_share(
+        sender,
+        &config,
+        is_fund_closed,
+        admin_owned_shares,
+        shares,
+        lp_total_supply,
+    )?;
+
+    save_lp_position(deps.storage, &config, sender, &remaining_lp_position)?;
+
+    let mut recipient_lp_position = LP_POSITIONS
+        .may_load(deps.storage, recipient)?
+        .unwrap_or_default();
+    recipient_lp_position
+        .lots
+        .extend(transferred_lots.iter().cloned());
+    // keep the oldest first order that redemptions rely on
+    recipient_lp_position
+        .lots
+        .sort_by_key(|lot| (lot.subscription_time, lot.lot_id));
+    save_lp_position(deps.storage, &config, recipient, &recipient_lp_position)?;
+
+    if sender == &config.admin {
+        admin_owned_shares -= shares;
+        ADMIN_OWNED_SHARES.save(deps.storage, &admin_owned_shares)?;
+    }
+    if recipient == &config.admin {
+        admin_owned_shares += shares;
+        ADMIN_OWNED_SHARES.save(deps.storage, &admin_owned_shares)?;
+    }
+
+    // LP tokens transferred to the admin are kept by the fund as part of the admin stake
+    let lp_token_transfer_messages = lp_token
+        .filter(|_| recipient != &config.admin)
+        .map(|lp_token| get_lp_token_transfer_message(&lp_token, shares, recipient))
+        .transpose()?;
+
+    Ok(Response::new()
+        .add_messages(lp_token_transfer_messages)
+        .add_attribute("method", "transfer_position")
+        .add_attribute("sender", sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("shares", shares.to_string())
+        .add_events(transferred_lots.iter().map(get_transferred_lot_event)))
+}
+
+/// Records a lot for the LP tokens an address holds beyond its lots, i.e. the ones it received
+/// outside the fund. Like untracked shares in a redemption the lot is priced at the worst case,
+/// bought now at the fund high-water mark, and its lockup starts now.
+pub fn get_lp_token_registration_response(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
+    sender: &Addr,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let Some(lp_token) = LP_TOKEN.may_load(deps.storage)? else {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The fund has no LP token",
+        )));
+    };
+    if sender == &config.admin {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The admin stake is held by the fund and has no LP tokens to register",
+        )));
+    }
+
+    let mut lp_position = load_lp_position(deps.as_ref(), sender, FPDecimal::zero())?;
+    let untracked_shares =
+        query_lp_token_balance(&deps.querier, &lp_token, sender)? - lp_position.shares();
+    if untracked_shares <= FPDecimal::zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Sender has no LP tokens without a lot",
+        )));
+    }
+
+    let fund_high_water_mark = FUND_HIGH_WATER_MARK
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let lot_id = LP_LOT_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    LP_LOT_COUNT.save(deps.storage, &lot_id)?;
+
+    lp_position.lots.push(LPLot {
+        lot_id,
+        shares: untracked_shares,
+        subscription_time: env.block.time,
+        subscription_amount: untracked_shares * fund_high_water_mark,
+        high_water_mark: fund_high_water_mark,
+    });
+    save_lp_position(deps.storage, &config, sender, &lp_position)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "register_lp_tokens")
+        .add_attribute("lot_id", lot_id.to_string())
+        .add_attribute("shares", untracked_shares.to_string()))
+}
+
+fn get_transferred_lot_event(transferred_lot: &LPLot) -> Event {
+    Event::new("transferred_lot")
+        .add_attribute("lot_id", transferred_lot.lot_id.to_string())
+        .add_attribute("shares", transferred_lot.shares.to_string())
+        .add_attribute(
+            "subscription_amount",
+            transferred_lot.subscription_amount.to_string(),
+        )
+}
@@ -4,7 +4,10 @@ use cosmwasm_std::{Coin, StdError};
 use injective_cosmwasm::{InjectiveQuerier, MarketId, OracleType, SubaccountId};
 use injective_math::FPDecimal;
 
-use crate::{state::Config, ContractError};
+use crate::{
+    state::{Config, MarketExposure},
+    ContractError,
+};
 
 use super::{
     derivative_position_helpers::{
@@ -79,10 +82,33 @@ pub fn get_derivative_base_in_quote(
     Ok(position_notional)
 }
 
+/// Signed notional of the fund position at the mark price, negative for shorts.
+pub fn get_derivative_position_exposure(
+    querier: &InjectiveQuerier,
+    subaccount_id: &SubaccountId,
+    market_id: &MarketId,
+) -> Result<FPDecimal, ContractError> {
+    let derivative_market_res = querier.query_derivative_market(market_id)?;
+    let vault_position = querier
+        .query_vanilla_subaccount_position(market_id, subaccount_id)?
+        .state;
+
+    Ok(match vault_position {
+        Some(position) if position.isLong => {
+            position.quantity * derivative_market_res.market.mark_price
+        }
+        Some(position) => {
+            FPDecimal::zero() - position.quantity * derivative_market_res.market.mark_price
+        }
+        None => FPDecimal::zero(),
+    })
+}
+
 pub fn get_fund_total_notional(
     querier: &InjectiveQuerier,
     config: &Config,
     denom_decimals: &HashMap<String, u64>,
+    mut market_exposures: Option<&mut Vec<MarketExposure>>,
 ) -> Result<FPDecimal, ContractError> {
     let quote_decimals = denom_decimals.get(&config.quote_denom).unwrap();
 
@@ -103,7 +129,7 @@ pub fn get_fund_total_notional(
             .spot_oracle_types
             .get(index)
             .expect("oracle type should exist");
-        vault_total_notional += get_spot_base_in_quote(
+        let notional = get_spot_base_in_quote(
             querier,
             &config.fund_subaccount_id,
             market_id,
@@ -112,12 +138,50 @@ pub fn get_fund_total_notional(
             oracle_type,
             None,
         )?;
+        vault_total_notional += notional;
+
+        if let Some(market_exposures) = market_exposures.as_mut() {
+            market_exposures.push(MarketExposure {
+                market_id: market_id.to_owned(),
+                notional,
+                exposure: notional,
+            });
+        }
     }
 
-    for (_, market_id) in config.derivative_market_ids.iter().enumerate() {
-        vault_total_notional +=
+    for market_id in config.derivative_market_ids.iter() {
+        let notional =
             get_derivative_base_in_quote(querier, &config.fund_subaccount_id, market_id)?;
+        vault_total_notional += notional;
+
+        if let Some(market_exposures) = market_exposures.as_mut() {
+            market_exposures.push(MarketExposure {
+                market_id: market_id.to_owned(),
+                notional,
+                exposure: get_derivative_position_exposure(
+                    querier,
+                    &config.fund_subaccount_id,
+                    market_id,
+                )?,
+            });
+        }
     }
 
     Ok(vault_total_notional)
 }
+
+pub fn get_nav_per_share(fund_total_notional: FPDecimal, lp_total_supply: FPDecimal) -> FPDecimal {
+    if lp_total_supply.is_zero() {
+        return FPDecimal::zero();
+    }
+
+    fund_total_notional / lp_total_supply
+}
+
+pub fn is_admin_owned_share_below_minimum(
+    admin_owned_shares: FPDecimal,
+    lp_total_supply: FPDecimal,
+) -> bool {
+    // the admin must keep at least 10% of the fund as skin in the game
+    admin_owned_shares * FPDecimal::from(10u128) < lp_total_supply
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+
+use cosmwasm_std::{Addr, Env, Storage, Timestamp};
+use cw_storage_plus::Item;
+use injective_cosmwasm::{MarketId, OracleType, SubaccountId};
+use injective_math::FPDecimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lp_actions::{fees::save_lp_position, redemptions::ONE_YEAR_IN_SECONDS},
+    state::{
+        Config, LPLot, LPPosition, ADMIN_FEE_POSITIONS, ADMIN_OWNED_SHARES, CONFIG,
+        LAST_CRYSTALLIZATION_TIME, LAST_MANAGEMENT_FEE_ACCRUAL, LP_LOT_COUNT,
+    },
+    ContractError,
+};
+
+type Migration = fn(&mut dyn Storage, &Env) -> Result<(), ContractError>;
+
+// ordered by the contract version that introduced the state change
+const MIGRATIONS: &[(&str, Migration)] = &[("1.1.0", migrate_v1_1_0)];
+
+/// Runs every migration newer than the stored version, up to and including the new one.
+pub fn run_migrations(
+    storage: &mut dyn Storage,
+    env: &Env,
+    stored_version: &str,
+    new_version: &str,
+) -> Result<(), ContractError> {
+    let stored_version = parse_version(stored_version)?;
+    let new_version = parse_version(new_version)?;
+    if stored_version > new_version {
+        return Err(ContractError::InvalidContractVersion {});
+    }
+
+    for (version, migration) in MIGRATIONS.iter() {
+        let version = parse_version(version)?;
+        if stored_version < version && version <= new_version {
+            migration(storage, env)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_version(version: &str) -> Result<(u64, u64, u64), ContractError> {
+    let parts = version
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|_| ContractError::InvalidContractVersion {})?;
+
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => Err(ContractError::InvalidContractVersion {}),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConfigV1_0_0 {
+    admin: Addr,
+    spot_oracle_types: Vec<OracleType>,
+    spot_market_ids: Vec<MarketId>,
+    derivative_market_ids: Vec<MarketId>,
+    quote_denom: String,
+    fund_subaccount_id: SubaccountId,
+    performance_fee_rate: FPDecimal,
+    min_yearly_roi_for_fees: FPDecimal,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LPPositionV1_0_0 {
+    shares: FPDecimal,
+    subscription_time: Timestamp,
+    subscription_amount: FPDecimal,
+}
+
+const CONFIG_V1_0_0: Item<ConfigV1_0_0> = Item::new("config");
+
+// the JSON map keys are read as strings, serde-json-wasm can't deserialize newtype map keys
+const LP_POSITIONS_V1_0_0: Item<HashMap<String, LPPositionV1_0_0>> = Item::new("lp_positions");
+
+const ADMIN_FEE_POSITIONS_V1_0_0: Item<HashMap<String, FPDecimal>> =
+    Item::new("admin_fee_positions");
+
+/// Fills in the new config fields with the previous behaviour, turns every LP position into a
+/// single lot and moves the positions from HashMap items into maps.
+fn migrate_v1_1_0(storage: &mut dyn Storage, env: &Env) -> Result<(), ContractError> {
+    let legacy_config = CONFIG_V1_0_0.load(storage)?;
+    let config = Config {
+        admin: legacy_config.admin,
+        spot_oracle_types: legacy_config.spot_oracle_types,
+        spot_market_ids: legacy_config.spot_market_ids,
+        derivative_market_ids: legacy_config.derivative_market_ids,
+        quote_denom: legacy_config.quote_denom,
+        fund_subaccount_id: legacy_config.fund_subaccount_id,
+        performance_fee_rate: legacy_config.performance_fee_rate,
+        management_fee_rate: FPDecimal::zero(),
+        min_yearly_roi_for_fees: legacy_config.min_yearly_roi_for_fees,
+        lockup_period: ONE_YEAR_IN_SECONDS,
+        early_redemption_policy: None,
+        performance_fee_collection: Default::default(),
+        crystallization_period: 0,
+    };
+    CONFIG.save(storage, &config)?;
+
+    let mut legacy_lp_positions = LP_POSITIONS_V1_0_0
+        .may_load(storage)?
+        .unwrap_or_default()
+        .into_iter()
+        .collect::<Vec<_>>();
+    // lot ids are handed out in address order to keep the migration deterministic
+    legacy_lp_positions.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    // the fund mark is left unset, so every lot is charged from its own mark until the first
+    // crystallization charges the fund
+    let mut lot_id = LP_LOT_COUNT.may_load(storage)?.unwrap_or_default();
+    for (address, legacy_lp_position) in legacy_lp_positions {
+        lot_id += 1;
+        let high_water_mark = if legacy_lp_position.shares.is_zero() {
+            FPDecimal::zero()
+        } else {
+            legacy_lp_position.subscription_amount / legacy_lp_position.shares
+        };
+
+        save_lp_position(
+            storage,
+            &config,
+            &Addr::unchecked(address),
+            &LPPosition {
+                lots: vec![LPLot {
+                    lot_id,
+                    shares: legacy_lp_position.shares,
+                    subscription_time: legacy_lp_position.subscription_time,
+                    subscription_amount: legacy_lp_position.subscription_amount,
+                    high_water_mark,
+                }],
+            },
+        )?;
+    }
+    LP_LOT_COUNT.save(storage, &lot_id)?;
+    LP_POSITIONS_V1_0_0.remove(storage);
+
+    if let Some(admin_fee_positions) = ADMIN_FEE_POSITIONS_V1_0_0.may_load(storage)? {
+        for (market_id, quantity) in admin_fee_positions.iter() {
+            ADMIN_FEE_POSITIONS.save(storage, market_id, quantity)?;
+        }
+        ADMIN_FEE_POSITIONS_V1_0_0.remove(storage);
+    }
+
+    if ADMIN_OWNED_SHARES.may_load(storage)?.is_none() {
+        ADMIN_OWNED_SHARES.save(storage, &FPDecimal::zero())?;
+    }
+
+    // fees only start accruing from the upgrade
+    LAST_MANAGEMENT_FEE_ACCRUAL.save(storage, &env.block.time)?;
+    LAST_CRYSTALLIZATION_TIME.save(storage, &env.block.time)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, str::FromStr};
+
+    use cosmwasm_std::{
+        testing::{mock_env, MockStorage},
+        Addr, Order, StdResult, Storage, Timestamp,
+    };
+    use injective_cosmwasm::{MarketId, OracleType, SubaccountId};
+    use injective_math::FPDecimal;
+
+    use super::{
+        run_migrations, ConfigV1_0_0, LPPositionV1_0_0, ADMIN_FEE_POSITIONS_V1_0_0, CONFIG_V1_0_0,
+        LP_POSITIONS_V1_0_0,
+    };
+    use crate::{
+        lp_actions::{fees::get_high_water_mark_key, redemptions::ONE_YEAR_IN_SECONDS},
+        state::{
+            PerformanceFeeCollection, ADMIN_FEE_POSITIONS, ADMIN_OWNED_SHARES, CONFIG,
+            FUND_HIGH_WATER_MARK, LAST_CRYSTALLIZATION_TIME, LAST_MANAGEMENT_FEE_ACCRUAL,
+            LOT_HIGH_WATER_MARKS, LP_LOT_COUNT, LP_POSITIONS,
+        },
+        ContractError,
+    };
+
+    const MARKET_ID: &str = "0x4ca0f92fc28be0c9761326016b5a1a2177dd6375558365116b5bdda9abc229ce";
+
+    fn dec(value: &str) -> FPDecimal {
+        FPDecimal::from_str(value).unwrap()
+    }
+
+    fn store_v1_0_0_state(storage: &mut dyn Storage) {
+        CONFIG_V1_0_0
+            .save(
+                storage,
+                &ConfigV1_0_0 {
+                    admin: Addr::unchecked("admin"),
+                    spot_oracle_types: vec![OracleType::PriceFeed],
+                    spot_market_ids: vec![MarketId::unchecked(MARKET_ID)],
+                    derivative_market_ids: vec![],
+                    quote_denom: "usdt".to_owned(),
+                    fund_subaccount_id: SubaccountId::unchecked(
+                        "0xaf79152ac5df276d9a8e1e2e22822f9713474902000000000000000000000000",
+                    ),
+                    performance_fee_rate: dec("0.2"),
+                    min_yearly_roi_for_fees: dec("0.1"),
+                },
+            )
+            .unwrap();
+
+        LP_POSITIONS_V1_0_0
+            .save(
+                storage,
+                &HashMap::from([
+                    (
+                        "lp_b".to_owned(),
+                        LPPositionV1_0_0 {
+                            shares: dec("300"),
+                            subscription_time: Timestamp::from_seconds(2_000),
+                            subscription_amount: dec("450"),
+                        },
+                    ),
+                    (
+                        "lp_a".to_owned(),
+                        LPPositionV1_0_0 {
+                            shares: dec("100"),
+                            subscription_time: Timestamp::from_seconds(1_000),
+                            subscription_amount: dec("100"),
+                        },
+                    ),
+                ]),
+            )
+            .unwrap();
+
+        ADMIN_FEE_POSITIONS_V1_0_0
+            .save(
+                storage,
+                &HashMap::from([(MARKET_ID.to_owned(), dec("1.5"))]),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_migrate_v1_1_0() {
+        let mut storage = MockStorage::new();
+        let env = mock_env();
+        store_v1_0_0_state(&mut storage);
+
+        run_migrations(&mut storage, &env, "1.0.0", "1.1.0").unwrap();
+
+        let config = CONFIG.load(&storage).unwrap();
+        assert_eq!(config.admin, Addr::unchecked("admin"));
+        assert_eq!(config.performance_fee_rate, dec("0.2"));
+        assert_eq!(config.min_yearly_roi_for_fees, dec("0.1"));
+        assert!(config.management_fee_rate.is_zero());
+        assert_eq!(config.lockup_period, ONE_YEAR_IN_SECONDS);
+        assert_eq!(config.early_redemption_policy, None);
+        assert_eq!(
+            config.performance_fee_collection,
+            PerformanceFeeCollection::ProRata
+        );
+        assert_eq!(config.crystallization_period, 0);
+
+        let lp_a_position = LP_POSITIONS
+            .load(&storage, &Addr::unchecked("lp_a"))
+            .unwrap();
+        assert_eq!(lp_a_position.lots.len(), 1);
+        assert_eq!(lp_a_position.lots[0].lot_id, 1);
+        assert_eq!(lp_a_position.lots[0].shares, dec("100"));
+        assert_eq!(
+            lp_a_position.lots[0].subscription_time,
+            Timestamp::from_seconds(1_000)
+        );
+        assert_eq!(lp_a_position.lots[0].subscription_amount, dec("100"));
+        assert_eq!(lp_a_position.lots[0].high_water_mark, dec("1"));
+
+        let lp_b_position = LP_POSITIONS
+            .load(&storage, &Addr::unchecked("lp_b"))
+            .unwrap();
+        assert_eq!(lp_b_position.lots.len(), 1);
+        assert_eq!(lp_b_position.lots[0].lot_id, 2);
+        assert_eq!(lp_b_position.lots[0].high_water_mark, dec("1.5"));
+
+        assert_eq!(LP_LOT_COUNT.load(&storage).unwrap(), 2);
+        assert!(FUND_HIGH_WATER_MARK.may_load(&storage).unwrap().is_none());
+        let lot_high_water_marks = LOT_HIGH_WATER_MARKS
+            .range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            lot_high_water_marks,
+            vec![
+                (get_high_water_mark_key(dec("1")), dec("100")),
+                (get_high_water_mark_key(dec("1.5")), dec("300")),
+            ]
+        );
+
+        let admin_fee_positions = ADMIN_FEE_POSITIONS
+            .range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            admin_fee_positions,
+            vec![(MARKET_ID.to_owned(), dec("1.5"))]
+        );
+
+        assert!(ADMIN_OWNED_SHARES.load(&storage).unwrap().is_zero());
+        assert_eq!(
+            LAST_MANAGEMENT_FEE_ACCRUAL.load(&storage).unwrap(),
+            env.block.time
+        );
+        assert_eq!(
+            LAST_CRYSTALLIZATION_TIME.load(&storage).unwrap(),
+            env.block.time
+        );
+
+        assert!(LP_POSITIONS_V1_0_0.may_load(&storage).unwrap().is_none());
+        assert!(ADMIN_FEE_POSITIONS_V1_0_0
+            .may_load(&storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_migrate_v1_1_0_without_lp_positions() {
+        let mut storage = MockStorage::new();
+        let env = mock_env();
+        store_v1_0_0_state(&mut storage);
+        LP_POSITIONS_V1_0_0.remove(&mut storage);
+        ADMIN_FEE_POSITIONS_V1_0_0.remove(&mut storage);
+
+        run_migrations(&mut storage, &env, "1.0.0", "1.1.0").unwrap();
+
+        assert_eq!(LP_LOT_COUNT.load(&storage).unwrap(), 0);
+        assert!(FUND_HIGH_WATER_MARK.may_load(&storage).unwrap().is_none());
+        assert!(ADMIN_FEE_POSITIONS
+            .range(&storage, None, None, Order::Ascending)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_migrations_skip_applied_versions() {
+        let mut storage = MockStorage::new();
+        let env = mock_env();
+        store_v1_0_0_state(&mut storage);
+
+        // the legacy state is left untouched when no migration is newer than the stored version
+        run_migrations(&mut storage, &env, "1.1.0", "1.1.0").unwrap();
+        run_migrations(&mut storage, &env, "1.1.0", "1.2.0").unwrap();
+        run_migrations(&mut storage, &env, "0.9.0", "1.0.5").unwrap();
+
+        assert!(LP_POSITIONS_V1_0_0.may_load(&storage).unwrap().is_some());
+        assert!(LP_LOT_COUNT.may_load(&storage).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_migrations_reject_downgrades() {
+        let mut storage = MockStorage::new();
+        store_v1_0_0_state(&mut storage);
+
+        let err = run_migrations(&mut storage, &mock_env(), "1.1.0", "1.0.0").unwrap_err();
+
+        assert!(matches!(err, ContractError::InvalidContractVersion {}));
+        assert!(LP_POSITIONS_V1_0_0.may_load(&storage).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_migrations_reject_malformed_versions() {
+        let mut storage = MockStorage::new();
+        store_v1_0_0_state(&mut storage);
+
+        for (stored_version, new_version) in [
+            ("1.0", "1.1.0"),
+            ("1.0.0", "1.1"),
+            ("1.0.0.0", "1.1.0"),
+            ("1.0.0", "v1.1.0"),
+            ("1.0.0", "1.1.0-beta"),
+            ("", "1.1.0"),
+        ] {
+            let err =
+                run_migrations(&mut storage, &mock_env(), stored_version, new_version).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidContractVersion {}));
+        }
+
+        assert!(LP_POSITIONS_V1_0_0.may_load(&storage).unwrap().is_some());
+    }
+}
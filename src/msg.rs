@@ -1,9 +1,15 @@
-use cosmwasm_std::CosmosMsg;
+use cosmwasm_std::{Addr, Coin, CosmosMsg, Timestamp, Uint128};
+use cw20::Cw20ReceiveMsg;
 use injective_cosmwasm::{InjectiveMsgWrapper, MarketId, OracleType, SubaccountId};
 use injective_math::FPDecimal;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{
+    Crystallization, DrawdownTrip, EarlyRedemptionPolicy, LPLot, LPPosition, LpToken, NavSnapshot,
+    PerformanceFeeCollection, RiskPolicy,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
     pub spot_oracle_types: Vec<OracleType>,
@@ -12,11 +18,36 @@ pub struct InstantiateMsg {
     pub quote_denom: String, // all markets must have this as the quote denom
     pub fund_subaccount_id: SubaccountId,
     pub performance_fee_rate: FPDecimal,
+    pub management_fee_rate: FPDecimal, // yearly, e.g. 0.02 means 2% of the fund per year
     pub min_yearly_roi_for_fees: FPDecimal, // e.g. 1.1 means min 10% yearly ROI before paying admin fees
+    pub lockup_period: u64,                 // in seconds, zero means no lockup
+    pub early_redemption_policy: Option<EarlyRedemptionPolicy>,
+    #[serde(default)]
+    pub performance_fee_collection: PerformanceFeeCollection,
+    #[serde(default)]
+    pub crystallization_period: u64, // in seconds, zero means fees are only charged on redemption
+    pub lp_token: Option<LpTokenInstantiation>, // positions are only kept in the fund ledger if not set
+    #[serde(default)]
+    pub risk_policy: RiskPolicy,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+pub enum LpTokenInstantiation {
+    Tokenfactory {},
+    InstantiateCw20 {
+        code_id: u64, // of a cw20-base compatible contract
+        name: String,
+        symbol: String,
+        decimals: u8,
+    },
+    LinkCw20 {
+        address: String, // the fund must be its only minter, without a cap, and it must have no supply
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     AdminExecuteMessages {
         injective_messages: Vec<CosmosMsg<InjectiveMsgWrapper>>,
@@ -24,13 +55,71 @@ pub enum ExecuteMsg {
     Subscribe {},
     Redeem {
         redeemer_subaccount_id: SubaccountId,
+        shares: Option<FPDecimal>, // redeems all of the selected lots if not set
+        lot_ids: Option<Vec<u64>>, // lots to redeem from in this order, oldest first if not set
+        #[serde(default)]
+        allow_early_redemption: bool, // accept the early redemption penalty on locked lots
     },
     AdminReceiveFeePositions {
         receiving_subaccount_id: SubaccountId,
     },
     CloseFund {},
+    UpdateLockupPeriod {
+        lockup_period: u64,
+    },
+    UpdateEarlyRedemptionPolicy {
+        early_redemption_policy: Option<EarlyRedemptionPolicy>,
+    },
+    UpdateCrystallizationPeriod {
+        crystallization_period: u64,
+    },
+    Receive(Cw20ReceiveMsg),
+    TransferPosition {
+        recipient: String,
+        shares: FPDecimal, // taken from the oldest lots first, must match the LP tokens sent if any
+    },
+    RegisterLpTokens {}, // records a lot for the LP tokens of the sender received outside the fund
+    ProposeNewAdmin {
+        new_admin: String,
+        expires_in: u64, // in seconds
+    },
+    AcceptAdmin {}, // with an LP token, the new admin sends its stake for the fund to hold
+    UpdateRoles {
+        // unset roles are held by the admin
+        trader: Option<String>,
+        operator: Option<String>,
+        guardian: Option<String>,
+    },
+    Pause {
+        reason: String,
+        #[serde(default)]
+        block_redemptions: bool,
+    },
+    Unpause {},
+    UpdateRiskPolicy {
+        risk_policy: RiskPolicy,
+    },
+    ResetDrawdownBreaker {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    Redeem {
+        redeemer_subaccount_id: SubaccountId,
+        lot_ids: Option<Vec<u64>>,
+        #[serde(default)]
+        allow_early_redemption: bool,
+    },
+    TransferPosition {
+        recipient: String, // receives the LP tokens along with the lots of the sender
+    },
+    AcceptAdmin {},
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SudoMsg {
@@ -41,4 +130,188 @@ pub enum SudoMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Ping {},
+    Config {},
+    PendingAdmin {},
+    Roles {},
+    PauseState {},
+    RiskPolicy {},
+    DrawdownBreaker {},
+    FundState {},
+    DenomDecimals {},
+    Nav {},
+    NavHistory {
+        start_after: Option<u64>, // block time in seconds
+        limit: Option<u32>,
+    },
+    LpPosition {
+        address: String,
+    },
+    AllLpPositions {
+        start_after: Option<String>, // LP address
+        limit: Option<u32>,
+    },
+    AllAdminFeePositions {
+        start_after: Option<MarketId>,
+        limit: Option<u32>,
+    },
+    SimulateRedeem {
+        address: String,
+        redeemer_subaccount_id: SubaccountId,
+        shares: Option<FPDecimal>,
+        lot_ids: Option<Vec<u64>>,
+        #[serde(default)]
+        allow_early_redemption: bool,
+    },
+    AccruedManagementFee {},
+    Crystallizations {
+        start_after: Option<u64>, // crystallization id
+        limit: Option<u32>,
+    },
+    SimulateSubscribe {
+        amount: Uint128,            // of the quote denom
+        subscriber: Option<String>, // only needed to simulate a subscription by the admin
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RolesResponse {
+    pub trader: Addr,
+    pub operator: Addr,
+    pub guardian: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DrawdownBreakerResponse {
+    pub peak_nav_per_share: Option<FPDecimal>,
+    pub drawdown_trip: Option<DrawdownTrip>, // set while the breaker is tripped
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FundStateResponse {
+    pub lp_total_supply: FPDecimal,
+    pub admin_owned_shares: FPDecimal,
+    pub is_fund_closed: bool,
+    pub lp_token: Option<LpToken>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LpPositionEntry {
+    pub address: Addr,
+    pub position: LPPosition,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AllLpPositionsResponse {
+    pub lp_positions: Vec<LpPositionEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AdminFeePosition {
+    pub market_id: MarketId,
+    pub quantity: FPDecimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AllAdminFeePositionsResponse {
+    pub admin_fee_positions: Vec<AdminFeePosition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NavHistoryResponse {
+    pub nav_snapshots: Vec<NavSnapshot>, // oldest first
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CrystallizationsResponse {
+    pub crystallizations: Vec<Crystallization>,
+    pub last_crystallization_time: Option<Timestamp>,
+    pub fund_high_water_mark: Option<FPDecimal>, // NAV per share performance fees are charged above
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DenomDecimals {
+    pub denom: String,
+    pub decimals: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DenomDecimalsResponse {
+    pub denom_decimals: Vec<DenomDecimals>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NavResponse {
+    pub nav: FPDecimal,
+    pub nav_per_share: FPDecimal, // zero while no LP shares exist
+    pub lp_total_supply: FPDecimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AccruedManagementFeeResponse {
+    pub accrued_shares: FPDecimal, // minted to the admin on the next subscription or redemption
+    pub accrued_notional: FPDecimal,
+    pub last_accrual_time: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LpLotResponse {
+    pub lot: LPLot,
+    pub value: FPDecimal, // lot value at the current NAV per share
+    pub unrealized_profit: FPDecimal,
+    pub annualized_roi: FPDecimal, // e.g. 0.1 means 10% profit per year
+    pub high_water_mark: FPDecimal, // the higher of the lot and fund marks
+    pub would_charge_performance_fee: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LpPositionResponse {
+    pub position: LPPosition,
+    pub value: FPDecimal,
+    pub unrealized_profit: FPDecimal,
+    pub lots: Vec<LpLotResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulatedPositionTransfer {
+    pub market_id: MarketId,
+    pub quantity: FPDecimal, // transferred to the redeemer, after the admin fee and penalty
+    pub admin_fee_quantity: FPDecimal,
+    pub penalty_quantity: FPDecimal, // always kept by the fund, even with a penalty recipient
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct SimulateRedeemResponse {
+    pub lp_shares_to_burn: FPDecimal,
+    pub total_redemption_notional: FPDecimal,
+    pub total_profits: FPDecimal,
+    pub performance_fee_notional: FPDecimal,
+    pub early_redemption_penalty_notional: FPDecimal,
+    pub redeemed_lots: Vec<RedeemedLot>,
+    pub coins: Vec<Coin>, // sent to the redeemer, after the admin fee and early redemption penalty
+    pub admin_fee_coins: Vec<Coin>,
+    pub penalty_coins: Vec<Coin>, // sent to the penalty recipient or kept by the fund
+    pub position_transfers: Vec<SimulatedPositionTransfer>,
+    pub failure_reasons: Vec<String>, // empty if a redemption would currently succeed
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct SimulateSubscribeResponse {
+    pub lp_shares_to_mint: FPDecimal,
+    pub nav_per_share: FPDecimal, // price per share the subscription is minted at
+    pub failure_reasons: Vec<String>, // empty if a subscription would currently succeed
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RedeemedLot {
+    pub lot_id: u64,
+    pub shares: FPDecimal,
+    pub subscription_time: Timestamp,
+    pub subscription_amount: FPDecimal, // cost basis of the redeemed shares
+    pub redemption_notional: FPDecimal,
+    pub profits: FPDecimal, // after the early redemption penalty
+    pub high_water_mark: FPDecimal,
+    pub profits_above_high_water_mark: FPDecimal, // the part of the profits performance fees apply to
+    pub performance_fee: FPDecimal,               // in quote notional
+    pub is_early_redemption: bool,
+    pub early_redemption_penalty: FPDecimal, // in quote notional
 }
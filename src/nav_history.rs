@@ -0,0 +1,57 @@
+use cosmwasm_std::{Deps, DepsMut, Env, Order, StdResult};
+use injective_cosmwasm::{InjectiveQuerier, InjectiveQueryWrapper};
+
+use crate::{
+    lp_actions::utils::{get_fund_total_notional, get_nav_per_share},
+    risk::drawdown::update_drawdown_breaker,
+    state::{Config, NavSnapshot, DENOM_DECIMALS, LP_TOTAL_SUPPLY, NAV_HISTORY},
+    ContractError,
+};
+
+pub const NAV_SNAPSHOT_INTERVAL: u64 = 60 * 60; // in seconds, between BeginBlocker snapshots
+
+pub fn store_nav_snapshot(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
+    config: &Config,
+) -> Result<NavSnapshot, ContractError> {
+    let querier = InjectiveQuerier::new(&deps.querier);
+    let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
+    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)?;
+
+    let mut market_exposures = vec![];
+    let nav = get_fund_total_notional(
+        &querier,
+        config,
+        &denom_decimals,
+        Some(&mut market_exposures),
+    )?;
+
+    let nav_snapshot = NavSnapshot {
+        time: env.block.time,
+        nav,
+        nav_per_share: get_nav_per_share(nav, lp_total_supply),
+        lp_total_supply,
+        market_exposures,
+    };
+
+    // a later snapshot in the same block replaces the earlier one
+    NAV_HISTORY.save(deps.storage, env.block.time.seconds(), &nav_snapshot)?;
+    update_drawdown_breaker(deps.storage, env, nav_snapshot.nav_per_share)?;
+
+    Ok(nav_snapshot)
+}
+
+pub fn is_nav_snapshot_due(deps: Deps<InjectiveQueryWrapper>, env: &Env) -> StdResult<bool> {
+    let last_snapshot_time = NAV_HISTORY
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?;
+
+    Ok(match last_snapshot_time {
+        Some(last_snapshot_time) => {
+            env.block.time.seconds() >= last_snapshot_time + NAV_SNAPSHOT_INTERVAL
+        }
+        None => true,
+    })
+}
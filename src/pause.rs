@@ -0,0 +1,59 @@
+use cosmwasm_std::{Addr, DepsMut, Env, Response, StdError, Storage};
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
+
+use crate::{
+    error::ContractError,
+    roles::ensure_role,
+    state::{PauseState, Role, CONFIG, PAUSE_STATE},
+};
+
+/// Fails while the fund is paused. Redemptions only fail if the pause also blocks them.
+pub fn ensure_not_paused(storage: &dyn Storage, is_redemption: bool) -> Result<(), ContractError> {
+    match PAUSE_STATE.may_load(storage)? {
+        Some(pause_state) if !is_redemption || pause_state.block_redemptions => {
+            Err(ContractError::FundPaused {})
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn pause(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: &Env,
+    sender: Addr,
+    reason: String,
+    block_redemptions: bool,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &config, &sender, Role::Guardian)?;
+
+    // pausing again updates the reason and whether redemptions are blocked
+    let pause_state = PauseState {
+        reason,
+        paused_at: env.block.time,
+        block_redemptions,
+    };
+    PAUSE_STATE.save(deps.storage, &pause_state)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "pause")
+        .add_attribute("reason", pause_state.reason)
+        .add_attribute("block_redemptions", block_redemptions.to_string()))
+}
+
+pub fn unpause(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    sender: Addr,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &config, &sender, Role::Guardian)?;
+
+    if PAUSE_STATE.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Fund is not paused",
+        )));
+    }
+    PAUSE_STATE.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("method", "unpause"))
+}
@@ -0,0 +1,441 @@
+use cosmwasm_std::{Deps, Env, Order, StdError, StdResult, Uint128};
+use cw_storage_plus::Bound;
+use injective_cosmwasm::{InjectiveQuerier, InjectiveQueryWrapper, MarketId, SubaccountId};
+use injective_math::FPDecimal;
+
+use crate::{
+    lp_actions::{
+        fees::{get_pending_management_fee_shares, is_charged_performance_fees},
+        lp_token::{get_lot_and_untracked_shares, load_lp_position, round_lp_shares},
+        redemptions::{
+            ensure_admin_keeps_minimum_share, ensure_valid_redemption, get_profits_per_year,
+            get_redemption_data, get_redemption_payout, should_charge_performance_fees,
+        },
+        subscriptions::get_token_mint_data,
+        utils::{get_fund_total_notional, get_nav_per_share, is_admin_owned_share_below_minimum},
+    },
+    msg::{
+        AccruedManagementFeeResponse, AdminFeePosition, AllAdminFeePositionsResponse,
+        AllLpPositionsResponse, CrystallizationsResponse, DenomDecimals, DenomDecimalsResponse,
+        FundStateResponse, LpLotResponse, LpPositionEntry, LpPositionResponse, NavHistoryResponse,
+        NavResponse, SimulateRedeemResponse, SimulateSubscribeResponse, SimulatedPositionTransfer,
+    },
+    pause::ensure_not_paused,
+    state::{
+        ADMIN_FEE_POSITIONS, ADMIN_OWNED_SHARES, CONFIG, CRYSTALLIZATIONS, DENOM_DECIMALS,
+        FUND_HIGH_WATER_MARK, IS_FUND_CLOSED, LAST_CRYSTALLIZATION_TIME,
+        LAST_MANAGEMENT_FEE_ACCRUAL, LP_POSITIONS, LP_TOKEN, LP_TOTAL_SUPPLY, NAV_HISTORY,
+    },
+    ContractError,
+};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn query_fund_state(deps: Deps<InjectiveQueryWrapper>) -> StdResult<FundStateResponse> {
+    Ok(FundStateResponse {
+        lp_total_supply: LP_TOTAL_SUPPLY.load(deps.storage)?,
+        admin_owned_shares: ADMIN_OWNED_SHARES
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        is_fund_closed: IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default(),
+        lp_token: LP_TOKEN.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_denom_decimals(deps: Deps<InjectiveQueryWrapper>) -> StdResult<DenomDecimalsResponse> {
+    let mut denom_decimals: Vec<DenomDecimals> = DENOM_DECIMALS
+        .load(deps.storage)?
+        .into_iter()
+        .map(|(denom, decimals)| DenomDecimals { denom, decimals })
+        .collect();
+    denom_decimals.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+    Ok(DenomDecimalsResponse { denom_decimals })
+}
+
+pub fn query_nav(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+) -> Result<NavResponse, ContractError> {
+    let querier = InjectiveQuerier::new(&deps.querier);
+    let config = CONFIG.load(deps.storage)?;
+
+    let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
+    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)?
+        + get_pending_management_fee_shares(deps, env, &config)?;
+
+    let nav = get_fund_total_notional(&querier, &config, &denom_decimals, None)?;
+
+    Ok(NavResponse {
+        nav,
+        nav_per_share: get_nav_per_share(nav, lp_total_supply),
+        lp_total_supply,
+    })
+}
+
+pub fn query_nav_history(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<NavHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let nav_snapshots = NAV_HISTORY
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, nav_snapshot)| nav_snapshot))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(NavHistoryResponse { nav_snapshots })
+}
+
+pub fn query_lp_position(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    address: String,
+) -> Result<LpPositionResponse, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    let lp_position = load_lp_position(deps, &address, FPDecimal::zero())?;
+    if lp_position.lots.is_empty() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "LP position does not exist",
+        )));
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let nav = query_nav(deps, env)?;
+    let fund_high_water_mark = FUND_HIGH_WATER_MARK
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let lots = lp_position
+        .lots
+        .iter()
+        .map(|lot| {
+            let value = lot.shares * nav.nav_per_share;
+            let unrealized_profit = value - lot.subscription_amount;
+            let profits_per_year =
+                get_profits_per_year(env, lot.subscription_time, unrealized_profit);
+            let annualized_roi = if lot.subscription_amount.is_zero() {
+                FPDecimal::zero()
+            } else {
+                profits_per_year / lot.subscription_amount
+            };
+            let high_water_mark = lot.get_high_water_mark(fund_high_water_mark);
+
+            LpLotResponse {
+                lot: lot.to_owned(),
+                value,
+                unrealized_profit,
+                annualized_roi,
+                high_water_mark,
+                would_charge_performance_fee: is_charged_performance_fees(&config, &address)
+                    && nav.nav_per_share > high_water_mark
+                    && should_charge_performance_fees(
+                        &config,
+                        lot.subscription_amount,
+                        profits_per_year,
+                    ),
+            }
+        })
+        .collect::<Vec<LpLotResponse>>();
+
+    let value = lp_position.shares() * nav.nav_per_share;
+
+    Ok(LpPositionResponse {
+        value,
+        unrealized_profit: value - lp_position.subscription_amount(),
+        position: lp_position,
+        lots,
+    })
+}
+
+pub fn query_all_lp_positions(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllLpPositionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+
+    let lp_positions = LP_POSITIONS
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(address, position)| LpPositionEntry { address, position }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllLpPositionsResponse { lp_positions })
+}
+
+pub fn query_all_admin_fee_positions(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<MarketId>,
+    limit: Option<u32>,
+) -> StdResult<AllAdminFeePositionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let admin_fee_positions = ADMIN_FEE_POSITIONS
+        .range(
+            deps.storage,
+            start_after
+                .as_ref()
+                .map(|market_id| Bound::exclusive(market_id.as_str())),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(market_id, quantity)| AdminFeePosition {
+                market_id: MarketId::unchecked(market_id),
+                quantity,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllAdminFeePositionsResponse {
+        admin_fee_positions,
+    })
+}
+
+pub fn query_simulate_redeem(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    address: String,
+    redeemer_subaccount_id: SubaccountId,
+    shares: Option<FPDecimal>,
+    lot_ids: Option<Vec<u64>>,
+    allow_early_redemption: bool,
+) -> Result<SimulateRedeemResponse, ContractError> {
+    let sender = deps.api.addr_validate(&address)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    // the pending management fee is minted to the admin before a redemption
+    let admin_owned_shares = ADMIN_OWNED_SHARES
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + get_pending_management_fee_shares(deps, env, &config)?;
+    let is_fund_closed = IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default();
+
+    let mut failure_reasons = vec![];
+    if let Err(err) = ensure_not_paused(deps.storage, true) {
+        failure_reasons.push(err.to_string());
+    }
+
+    // the LP tokens are still held by the redeemer before they are sent in
+    let lp_position = load_lp_position(deps, &sender, FPDecimal::zero())?;
+
+    // with LP tokens, shares is the amount of LP tokens that would be sent in, except for the
+    // admin stake which the fund holds
+    let lot_and_untracked_shares = match (LP_TOKEN.may_load(deps.storage)?, shares) {
+        (Some(_), _) if sender == config.admin => Ok((shares, FPDecimal::zero())),
+        (Some(_), Some(lp_tokens)) => get_lot_and_untracked_shares(&lp_position, shares, lp_tokens)
+            .map(|(lot_shares, untracked_shares)| (Some(lot_shares), untracked_shares)),
+        (Some(_), None) => Err(ContractError::Std(StdError::generic_err(
+            "Shares must be set to the LP tokens to redeem",
+        ))),
+        (None, _) => Ok((shares, FPDecimal::zero())),
+    };
+
+    let redemption = match lot_and_untracked_shares.and_then(|(shares, untracked_shares)| {
+        get_redemption_data(
+            deps,
+            env,
+            &config,
+            &sender,
+            lp_position,
+            redeemer_subaccount_id,
+            shares,
+            lot_ids,
+            allow_early_redemption,
+            untracked_shares,
+        )
+    }) {
+        Ok(redemption) => redemption,
+        Err(err) => {
+            failure_reasons.push(err.to_string());
+            return Ok(SimulateRedeemResponse {
+                failure_reasons,
+                ..SimulateRedeemResponse::default()
+            });
+        }
+    };
+
+    if let Err(err) = ensure_valid_redemption(env, &config, &redemption) {
+        failure_reasons.push(err.to_string());
+    }
+    if let Err(err) = ensure_admin_keeps_minimum_share(
+        &sender,
+        &config,
+        is_fund_closed,
+        admin_owned_shares,
+        redemption.lp_shares_to_burn,
+        redemption.lp_total_supply - redemption.lp_shares_to_burn,
+    ) {
+        failure_reasons.push(err.to_string());
+    }
+
+    let payout = get_redemption_payout(&config, &redemption);
+
+    let get_market_quantity = |quantities: &[(MarketId, FPDecimal)], market_id: &MarketId| {
+        quantities
+            .iter()
+            .find(|(quantity_market_id, _)| quantity_market_id == market_id)
+            .map(|(_, quantity)| *quantity)
+            .unwrap_or_default()
+    };
+    let position_transfers = payout
+        .redeemer_position_transfers
+        .iter()
+        .map(|position_transfer| SimulatedPositionTransfer {
+            market_id: position_transfer.market_id.to_owned(),
+            quantity: position_transfer.quantity,
+            admin_fee_quantity: get_market_quantity(
+                &payout.admin_fee_position_quantities,
+                &position_transfer.market_id,
+            ),
+            penalty_quantity: get_market_quantity(
+                &payout.penalty_position_quantities,
+                &position_transfer.market_id,
+            ),
+        })
+        .collect();
+
+    Ok(SimulateRedeemResponse {
+        lp_shares_to_burn: redemption.lp_shares_to_burn,
+        total_redemption_notional: redemption.total_redemption_notional,
+        total_profits: redemption.total_profits,
+        performance_fee_notional: redemption.performance_fee_notional,
+        early_redemption_penalty_notional: redemption.early_redemption_penalty_notional,
+        redeemed_lots: redemption.redeemed_lots,
+        coins: payout.redeemer_funds,
+        admin_fee_coins: payout.admin_fee_funds,
+        penalty_coins: payout.penalty_funds,
+        position_transfers,
+        failure_reasons,
+    })
+}
+
+pub fn query_simulate_subscribe(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    amount: Uint128,
+    subscriber: Option<String>,
+) -> Result<SimulateSubscribeResponse, ContractError> {
+    let querier = InjectiveQuerier::new(&deps.querier);
+    let config = CONFIG.load(deps.storage)?;
+
+    // the pending management fee is minted to the admin before a subscription
+    let pending_management_fee_shares = get_pending_management_fee_shares(deps, env, &config)?;
+
+    let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
+    let lp_total_supply = LP_TOTAL_SUPPLY.load(deps.storage)? + pending_management_fee_shares;
+    let is_fund_closed = IS_FUND_CLOSED.may_load(deps.storage)?.unwrap_or_default();
+
+    let mut failure_reasons = vec![];
+    if is_fund_closed {
+        failure_reasons.push("Fund is closed".to_string());
+    }
+    if let Err(err) = ensure_not_paused(deps.storage, false) {
+        failure_reasons.push(err.to_string());
+    }
+
+    let fund_total_notional = get_fund_total_notional(&querier, &config, &denom_decimals, None)?;
+    let lp_shares_to_mint =
+        match get_token_mint_data(amount.into(), fund_total_notional, lp_total_supply) {
+            Ok(lp_shares_to_mint) => lp_shares_to_mint,
+            Err(err) => {
+                failure_reasons.push(err.to_string());
+                return Ok(SimulateSubscribeResponse {
+                    failure_reasons,
+                    ..SimulateSubscribeResponse::default()
+                });
+            }
+        };
+    let lp_shares_to_mint = round_lp_shares(deps.storage, lp_shares_to_mint)?;
+    if lp_shares_to_mint.is_zero() {
+        failure_reasons.push("Insufficient funds to mint LP tokens".to_string());
+        return Ok(SimulateSubscribeResponse {
+            failure_reasons,
+            ..SimulateSubscribeResponse::default()
+        });
+    }
+
+    let nav_per_share = if lp_total_supply.is_zero() {
+        FPDecimal::from(amount) / lp_shares_to_mint
+    } else {
+        get_nav_per_share(fund_total_notional, lp_total_supply)
+    };
+
+    let is_subscriber_the_admin = match subscriber {
+        Some(subscriber) => deps.api.addr_validate(&subscriber)? == config.admin,
+        None => false,
+    };
+    let mut admin_owned_shares = ADMIN_OWNED_SHARES
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + pending_management_fee_shares;
+    if is_subscriber_the_admin {
+        admin_owned_shares += lp_shares_to_mint;
+    }
+    if is_admin_owned_share_below_minimum(admin_owned_shares, lp_total_supply + lp_shares_to_mint) {
+        failure_reasons.push("Admin must own at least 10% of fund".to_string());
+    }
+
+    Ok(SimulateSubscribeResponse {
+        lp_shares_to_mint,
+        nav_per_share,
+        failure_reasons,
+    })
+}
+
+pub fn query_accrued_management_fee(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+) -> Result<AccruedManagementFeeResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let accrued_shares = get_pending_management_fee_shares(deps, env, &config)?;
+
+    Ok(AccruedManagementFeeResponse {
+        accrued_shares,
+        accrued_notional: accrued_shares * query_nav(deps, env)?.nav_per_share,
+        last_accrual_time: LAST_MANAGEMENT_FEE_ACCRUAL.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_crystallizations(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<CrystallizationsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let crystallizations = CRYSTALLIZATIONS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, crystallization)| crystallization))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(CrystallizationsResponse {
+        crystallizations,
+        last_crystallization_time: LAST_CRYSTALLIZATION_TIME.may_load(deps.storage)?,
+        fund_high_water_mark: FUND_HIGH_WATER_MARK.may_load(deps.storage)?,
+    })
+}
@@ -0,0 +1,149 @@
+use cosmwasm_std::{Addr, CosmosMsg, Deps, DepsMut, Env, Response, StdError, StdResult, Storage};
+use injective_cosmwasm::{
+    InjectiveMsg, InjectiveMsgWrapper, InjectiveQuerier, InjectiveQueryWrapper,
+};
+use injective_math::FPDecimal;
+
+use crate::{
+    error::ContractError,
+    lp_actions::utils::{get_fund_total_notional, get_nav_per_share},
+    risk::order_policy::is_buy_order,
+    roles::ensure_role,
+    state::{
+        Config, DrawdownTrip, Role, CONFIG, DENOM_DECIMALS, DRAWDOWN_TRIP, LP_TOTAL_SUPPLY,
+        PEAK_NAV_PER_SHARE, RISK_POLICY,
+    },
+};
+
+pub fn get_current_nav_per_share(
+    deps: Deps<InjectiveQueryWrapper>,
+    config: &Config,
+) -> Result<FPDecimal, ContractError> {
+    let querier = InjectiveQuerier::new(&deps.querier);
+    let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
+    let nav = get_fund_total_notional(&querier, config, &denom_decimals, None)?;
+
+    Ok(get_nav_per_share(nav, LP_TOTAL_SUPPLY.load(deps.storage)?))
+}
+
+/// Scales the peak NAV per share down by the dilution of newly minted fee shares, so fees
+/// paid to the admin don't count as a drawdown.
+pub fn rebase_peak_nav_per_share(
+    storage: &mut dyn Storage,
+    lp_total_supply: FPDecimal,
+    fee_shares: FPDecimal,
+) -> StdResult<()> {
+    if fee_shares.is_zero() {
+        return Ok(());
+    }
+
+    if let Some(peak_nav_per_share) = PEAK_NAV_PER_SHARE.may_load(storage)? {
+        PEAK_NAV_PER_SHARE.save(
+            storage,
+            &(peak_nav_per_share * lp_total_supply / (lp_total_supply + fee_shares)),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Raises the peak NAV per share and trips the breaker once the drawdown from the peak
+/// goes beyond the risk policy threshold. A tripped breaker stays tripped until a reset.
+pub fn update_drawdown_breaker(
+    storage: &mut dyn Storage,
+    env: &Env,
+    nav_per_share: FPDecimal,
+) -> Result<Option<DrawdownTrip>, ContractError> {
+    // an empty fund has no NAV per share to draw down from
+    if nav_per_share.is_zero() {
+        return Ok(DRAWDOWN_TRIP.may_load(storage)?);
+    }
+
+    let peak_nav_per_share = PEAK_NAV_PER_SHARE
+        .may_load(storage)?
+        .unwrap_or_default()
+        .max(nav_per_share);
+    PEAK_NAV_PER_SHARE.save(storage, &peak_nav_per_share)?;
+
+    if let Some(drawdown_trip) = DRAWDOWN_TRIP.may_load(storage)? {
+        return Ok(Some(drawdown_trip));
+    }
+
+    let max_drawdown = RISK_POLICY
+        .may_load(storage)?
+        .unwrap_or_default()
+        .max_drawdown;
+    let Some(max_drawdown) = max_drawdown else {
+        return Ok(None);
+    };
+
+    if nav_per_share >= peak_nav_per_share * (FPDecimal::one() - max_drawdown) {
+        return Ok(None);
+    }
+
+    let drawdown_trip = DrawdownTrip {
+        tripped_at: env.block.time,
+        peak_nav_per_share,
+        nav_per_share,
+    };
+    DRAWDOWN_TRIP.save(storage, &drawdown_trip)?;
+
+    Ok(Some(drawdown_trip))
+}
+
+/// While the breaker is tripped the trader can only cancel orders, place reduce-only
+/// derivative orders and sell spot holdings.
+pub fn ensure_risk_reducing_messages(
+    msgs: &[CosmosMsg<InjectiveMsgWrapper>],
+) -> Result<(), ContractError> {
+    for msg in msgs {
+        let is_risk_reducing = match msg {
+            CosmosMsg::Custom(InjectiveMsgWrapper {
+                msg_data:
+                    InjectiveMsg::BatchUpdateOrders {
+                        spot_orders_to_create,
+                        derivative_orders_to_create,
+                        ..
+                    },
+                ..
+            }) => {
+                spot_orders_to_create
+                    .iter()
+                    .all(|order| !is_buy_order(&order.order_type))
+                    && derivative_orders_to_create
+                        .iter()
+                        .all(|order| order.is_reduce_only())
+            }
+            _ => false,
+        };
+
+        if !is_risk_reducing {
+            return Err(ContractError::DrawdownBreakerTripped {});
+        }
+    }
+
+    Ok(())
+}
+
+pub fn reset_drawdown_breaker(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    sender: Addr,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &config, &sender, Role::Guardian)?;
+
+    if DRAWDOWN_TRIP.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Drawdown breaker is not tripped",
+        )));
+    }
+
+    // the drawdown is measured from the NAV per share at the reset from now on
+    let nav_per_share = get_current_nav_per_share(deps.as_ref(), &config)?;
+    PEAK_NAV_PER_SHARE.save(deps.storage, &nav_per_share)?;
+    DRAWDOWN_TRIP.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("method", "reset_drawdown_breaker")
+        .add_attribute("peak_nav_per_share", nav_per_share.to_string()))
+}
@@ -0,0 +1,127 @@
+use cosmwasm_std::{Deps, StdError};
+use injective_cosmwasm::{InjectiveQuerier, InjectiveQueryWrapper, MarketId};
+use injective_math::FPDecimal;
+
+use crate::{
+    error::ContractError,
+    lp_actions::utils::get_fund_total_notional,
+    state::{Config, RiskPolicy, DENOM_DECIMALS},
+};
+
+use super::order_policy::OrderToCheck;
+
+/// Fund exposure in quote assuming every new order fills.
+pub struct ProjectedExposure {
+    pub nav: FPDecimal,
+    pub gross_exposure: FPDecimal,
+    pub net_exposure: FPDecimal,
+}
+
+pub fn has_exposure_limits(risk_policy: &RiskPolicy) -> bool {
+    risk_policy.max_leverage.is_some()
+        || risk_policy.max_market_concentration.is_some()
+        || risk_policy.max_derivative_notional_ratio.is_some()
+}
+
+/// Projects the exposure of each market after the new orders and checks it against the
+/// limits relative to NAV. Orders on both sides of a market are not netted against each
+/// other, the side that would grow the exposure the most is assumed to fill. A fund already
+/// beyond a limit can still trade as long as the orders don't grow the exposure it limits.
+pub fn ensure_projected_exposure_within_limits(
+    deps: Deps<InjectiveQueryWrapper>,
+    config: &Config,
+    risk_policy: &RiskPolicy,
+    orders: &[OrderToCheck],
+) -> Result<ProjectedExposure, ContractError> {
+    let querier = InjectiveQuerier::new(&deps.querier);
+    let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
+
+    let mut market_exposures = vec![];
+    let nav = get_fund_total_notional(
+        &querier,
+        config,
+        &denom_decimals,
+        Some(&mut market_exposures),
+    )?;
+
+    let mut current_gross_exposure = FPDecimal::zero();
+    let mut current_derivative_exposure = FPDecimal::zero();
+    let mut gross_exposure = FPDecimal::zero();
+    let mut net_exposure = FPDecimal::zero();
+    let mut derivative_exposure = FPDecimal::zero();
+
+    for market_exposure in market_exposures.iter() {
+        let is_derivative_market = config
+            .derivative_market_ids
+            .contains(&market_exposure.market_id);
+
+        let current_exposure = market_exposure.exposure;
+
+        let (buy_notional, sell_notional) = get_order_notionals(orders, &market_exposure.market_id);
+        let projected_exposure = current_exposure + buy_notional - sell_notional;
+        let worst_case_exposure = (current_exposure + buy_notional)
+            .abs()
+            .max((current_exposure - sell_notional).abs());
+
+        if let Some(max_market_concentration) = risk_policy.max_market_concentration {
+            if worst_case_exposure > current_exposure.abs()
+                && worst_case_exposure > nav * max_market_concentration
+            {
+                return Err(ContractError::Std(StdError::generic_err(format!(
+                    "Exposure of {} in market {} would exceed the concentration limit",
+                    worst_case_exposure,
+                    market_exposure.market_id.as_str()
+                ))));
+            }
+        }
+
+        current_gross_exposure += current_exposure.abs();
+        gross_exposure += worst_case_exposure;
+        net_exposure += projected_exposure;
+        if is_derivative_market {
+            current_derivative_exposure += current_exposure.abs();
+            derivative_exposure += worst_case_exposure;
+        }
+    }
+
+    if let Some(max_leverage) = risk_policy.max_leverage {
+        if gross_exposure > current_gross_exposure && gross_exposure > nav * max_leverage {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Gross exposure of {gross_exposure} would exceed the leverage limit"
+            ))));
+        }
+    }
+
+    if let Some(max_derivative_notional_ratio) = risk_policy.max_derivative_notional_ratio {
+        if derivative_exposure > current_derivative_exposure
+            && derivative_exposure > nav * max_derivative_notional_ratio
+        {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Derivative exposure of {derivative_exposure} would exceed the derivative notional limit"
+            ))));
+        }
+    }
+
+    Ok(ProjectedExposure {
+        nav,
+        gross_exposure,
+        net_exposure,
+    })
+}
+
+fn get_order_notionals(orders: &[OrderToCheck], market_id: &MarketId) -> (FPDecimal, FPDecimal) {
+    orders
+        .iter()
+        .filter(|order| order.market_id == market_id)
+        .fold(
+            (FPDecimal::zero(), FPDecimal::zero()),
+            |(buy_notional, sell_notional), order| {
+                let notional = order.price * order.quantity;
+                if order.is_buy {
+                    (buy_notional + notional, sell_notional)
+                } else {
+                    (buy_notional, sell_notional + notional)
+                }
+            },
+        )
+}
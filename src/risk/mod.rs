@@ -0,0 +1,3 @@
+pub mod drawdown;
+pub mod exposure;
+pub mod order_policy;
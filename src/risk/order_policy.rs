@@ -0,0 +1,303 @@
+use cosmwasm_std::{Addr, CosmosMsg, Deps, DepsMut, Env, Response, StdError};
+use injective_cosmwasm::{
+    InjectiveMsg, InjectiveMsgWrapper, InjectiveQuerier, InjectiveQueryWrapper, MarketId,
+    OrderData, OrderType, SubaccountId,
+};
+use injective_math::FPDecimal;
+
+use crate::{
+    error::ContractError,
+    lp_actions::oracle_price::get_oracle_price,
+    risk::exposure::{
+        ensure_projected_exposure_within_limits, has_exposure_limits, ProjectedExposure,
+    },
+    roles::ensure_role,
+    state::{Config, RiskPolicy, Role, CONFIG, DENOM_DECIMALS, RISK_POLICY},
+};
+
+/// An order to be created, reduced to what the policy checks.
+pub struct OrderToCheck<'a> {
+    pub market_id: &'a MarketId,
+    pub subaccount_id: &'a SubaccountId,
+    pub price: FPDecimal,
+    pub quantity: FPDecimal,
+    pub is_buy: bool,
+}
+
+pub fn is_buy_order(order_type: &OrderType) -> bool {
+    matches!(
+        order_type,
+        OrderType::Buy | OrderType::BuyPo | OrderType::BuyAtomic
+    )
+}
+
+pub fn ensure_valid_risk_policy(
+    config: &Config,
+    risk_policy: &RiskPolicy,
+) -> Result<(), ContractError> {
+    let has_invalid_limit = [
+        risk_policy.max_price_deviation,
+        risk_policy.max_leverage,
+        risk_policy.max_market_concentration,
+        risk_policy.max_derivative_notional_ratio,
+        risk_policy.max_drawdown,
+    ]
+    .iter()
+    .any(|limit| limit.is_some_and(|limit| limit <= FPDecimal::zero()));
+    let has_invalid_quantity_cap = risk_policy.market_quantity_caps.iter().any(|cap| {
+        cap.max_quantity <= FPDecimal::zero()
+            || (!config.spot_market_ids.contains(&cap.market_id)
+                && !config.derivative_market_ids.contains(&cap.market_id))
+    });
+
+    let has_invalid_max_drawdown = risk_policy
+        .max_drawdown
+        .is_some_and(|max_drawdown| max_drawdown >= FPDecimal::one());
+
+    if has_invalid_limit || has_invalid_quantity_cap || has_invalid_max_drawdown {
+        return Err(ContractError::InvalidRiskPolicy {});
+    }
+
+    Ok(())
+}
+
+pub fn update_risk_policy(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    sender: Addr,
+    risk_policy: RiskPolicy,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &config, &sender, Role::Operator)?;
+
+    ensure_valid_risk_policy(&config, &risk_policy)?;
+
+    RISK_POLICY.save(deps.storage, &risk_policy)?;
+
+    Ok(Response::new().add_attribute("method", "update_risk_policy"))
+}
+
+/// Only order batches for the fund's own markets and subaccount are allowed, every new order
+/// must stay within the price band and the orders of each market within its quantity cap.
+/// The projected exposure is returned when the policy has exposure limits.
+pub fn ensure_messages_within_risk_policy(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    config: &Config,
+    msgs: &[CosmosMsg<InjectiveMsgWrapper>],
+) -> Result<Option<ProjectedExposure>, ContractError> {
+    let risk_policy = RISK_POLICY.may_load(deps.storage)?.unwrap_or_default();
+    let mut orders_to_create = vec![];
+
+    for msg in msgs {
+        let CosmosMsg::Custom(InjectiveMsgWrapper {
+            msg_data:
+                InjectiveMsg::BatchUpdateOrders {
+                    sender,
+                    subaccount_id,
+                    spot_market_ids_to_cancel_all,
+                    derivative_market_ids_to_cancel_all,
+                    spot_orders_to_cancel,
+                    derivative_orders_to_cancel,
+                    spot_orders_to_create,
+                    derivative_orders_to_create,
+                },
+            ..
+        }) = msg
+        else {
+            return Err(ContractError::Unauthorized {});
+        };
+
+        if sender != &env.contract.address {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if let Some(subaccount_id) = subaccount_id {
+            ensure_fund_subaccount(config, subaccount_id)?;
+        }
+
+        for market_id in spot_market_ids_to_cancel_all {
+            ensure_fund_market(&config.spot_market_ids, market_id)?;
+        }
+        for market_id in derivative_market_ids_to_cancel_all {
+            ensure_fund_market(&config.derivative_market_ids, market_id)?;
+        }
+
+        ensure_valid_orders_to_cancel(config, &config.spot_market_ids, spot_orders_to_cancel)?;
+        ensure_valid_orders_to_cancel(
+            config,
+            &config.derivative_market_ids,
+            derivative_orders_to_cancel,
+        )?;
+
+        for order in spot_orders_to_create {
+            let order = OrderToCheck {
+                market_id: &order.market_id,
+                subaccount_id: &order.order_info.subaccount_id,
+                price: order.order_info.price,
+                quantity: order.order_info.quantity,
+                is_buy: is_buy_order(&order.order_type),
+            };
+            ensure_fund_market(&config.spot_market_ids, order.market_id)?;
+
+            let reference_price = get_spot_reference_price(deps, config, order.market_id)?;
+            ensure_order_within_risk_policy(config, &risk_policy, &order, reference_price)?;
+            orders_to_create.push(order);
+        }
+
+        for order in derivative_orders_to_create {
+            let order = OrderToCheck {
+                market_id: &order.market_id,
+                subaccount_id: &order.order_info.subaccount_id,
+                price: order.order_info.price,
+                quantity: order.order_info.quantity,
+                is_buy: is_buy_order(&order.order_type),
+            };
+            ensure_fund_market(&config.derivative_market_ids, order.market_id)?;
+
+            let querier = InjectiveQuerier::new(&deps.querier);
+            let reference_price = querier
+                .query_derivative_market(order.market_id)?
+                .market
+                .mark_price;
+            ensure_order_within_risk_policy(config, &risk_policy, &order, reference_price)?;
+            orders_to_create.push(order);
+        }
+    }
+
+    ensure_orders_within_quantity_caps(&risk_policy, &orders_to_create)?;
+
+    if !has_exposure_limits(&risk_policy) {
+        return Ok(None);
+    }
+
+    Ok(Some(ensure_projected_exposure_within_limits(
+        deps,
+        config,
+        &risk_policy,
+        &orders_to_create,
+    )?))
+}
+
+fn ensure_fund_subaccount(
+    config: &Config,
+    subaccount_id: &SubaccountId,
+) -> Result<(), ContractError> {
+    if subaccount_id != &config.fund_subaccount_id {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Orders must use the fund subaccount, got {}",
+            subaccount_id.as_str()
+        ))));
+    }
+
+    Ok(())
+}
+
+fn ensure_fund_market(market_ids: &[MarketId], market_id: &MarketId) -> Result<(), ContractError> {
+    if !market_ids.contains(market_id) {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Market {} is not traded by the fund",
+            market_id.as_str()
+        ))));
+    }
+
+    Ok(())
+}
+
+fn ensure_valid_orders_to_cancel(
+    config: &Config,
+    market_ids: &[MarketId],
+    orders_to_cancel: &[OrderData],
+) -> Result<(), ContractError> {
+    for order in orders_to_cancel {
+        ensure_fund_market(market_ids, &order.market_id)?;
+        ensure_fund_subaccount(config, &order.subaccount_id)?;
+    }
+
+    Ok(())
+}
+
+fn ensure_order_within_risk_policy(
+    config: &Config,
+    risk_policy: &RiskPolicy,
+    order: &OrderToCheck,
+    reference_price: FPDecimal,
+) -> Result<(), ContractError> {
+    ensure_fund_subaccount(config, order.subaccount_id)?;
+
+    // market orders are checked too, their price is the worst price they may fill at
+    if let Some(max_price_deviation) = risk_policy.max_price_deviation {
+        let max_price_difference = reference_price * max_price_deviation;
+        let price_difference = if order.price > reference_price {
+            order.price - reference_price
+        } else {
+            reference_price - order.price
+        };
+
+        if reference_price <= FPDecimal::zero() || price_difference > max_price_difference {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Order price {} is outside the allowed band around {} in market {}",
+                order.price,
+                reference_price,
+                order.market_id.as_str()
+            ))));
+        }
+    }
+
+    Ok(())
+}
+
+/// The caps apply to the quantity of all orders created in a market together, so they can't
+/// be bypassed by splitting an order.
+fn ensure_orders_within_quantity_caps(
+    risk_policy: &RiskPolicy,
+    orders: &[OrderToCheck],
+) -> Result<(), ContractError> {
+    for cap in risk_policy.market_quantity_caps.iter() {
+        let quantity = orders
+            .iter()
+            .filter(|order| order.market_id == &cap.market_id)
+            .fold(FPDecimal::zero(), |quantity, order| {
+                quantity + order.quantity
+            });
+
+        if quantity > cap.max_quantity {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Order quantity {} exceeds the cap of {} in market {}",
+                quantity,
+                cap.max_quantity,
+                cap.market_id.as_str()
+            ))));
+        }
+    }
+
+    Ok(())
+}
+
+fn get_spot_reference_price(
+    deps: Deps<InjectiveQueryWrapper>,
+    config: &Config,
+    market_id: &MarketId,
+) -> Result<FPDecimal, ContractError> {
+    let querier = InjectiveQuerier::new(&deps.querier);
+    let denom_decimals = DENOM_DECIMALS.load(deps.storage)?;
+
+    let market = querier
+        .query_spot_market(market_id)?
+        .market
+        .expect("market should be available");
+    let oracle_type = config
+        .spot_market_ids
+        .iter()
+        .position(|id| id == market_id)
+        .and_then(|index| config.spot_oracle_types.get(index))
+        .expect("oracle type should exist");
+
+    Ok(get_oracle_price(
+        &querier,
+        oracle_type,
+        &market.base_denom,
+        &market.quote_denom,
+        *denom_decimals.get(&market.base_denom).unwrap(),
+        *denom_decimals.get(&market.quote_denom).unwrap(),
+    )?)
+}
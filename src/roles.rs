@@ -0,0 +1,67 @@
+use cosmwasm_std::{ensure_eq, Addr, DepsMut, Response, StdResult, Storage};
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
+
+use crate::{
+    error::ContractError,
+    msg::RolesResponse,
+    state::{Config, Role, Roles, CONFIG, ROLES},
+};
+
+/// Address holding the role, the admin holds every role that was not assigned.
+pub fn get_role_address(storage: &dyn Storage, config: &Config, role: Role) -> StdResult<Addr> {
+    let roles = ROLES.may_load(storage)?.unwrap_or_default();
+    let address = match role {
+        Role::Trader => roles.trader,
+        Role::Operator => roles.operator,
+        Role::Guardian => roles.guardian,
+    };
+
+    Ok(address.unwrap_or_else(|| config.admin.to_owned()))
+}
+
+pub fn ensure_role(
+    storage: &dyn Storage,
+    config: &Config,
+    sender: &Addr,
+    role: Role,
+) -> Result<(), ContractError> {
+    if sender != &get_role_address(storage, config, role)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+pub fn update_roles(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    sender: Addr,
+    trader: Option<String>,
+    operator: Option<String>,
+    guardian: Option<String>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_eq!(sender, config.admin, ContractError::Unauthorized {});
+
+    let roles = Roles {
+        trader: trader.map(|a| deps.api.addr_validate(&a)).transpose()?,
+        operator: operator.map(|a| deps.api.addr_validate(&a)).transpose()?,
+        guardian: guardian.map(|a| deps.api.addr_validate(&a)).transpose()?,
+    };
+    ROLES.save(deps.storage, &roles)?;
+
+    let roles = get_roles(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_roles")
+        .add_attribute("trader", roles.trader)
+        .add_attribute("operator", roles.operator)
+        .add_attribute("guardian", roles.guardian))
+}
+
+pub fn get_roles(storage: &dyn Storage, config: &Config) -> StdResult<RolesResponse> {
+    Ok(RolesResponse {
+        trader: get_role_address(storage, config, Role::Trader)?,
+        operator: get_role_address(storage, config, Role::Operator)?,
+        guardian: get_role_address(storage, config, Role::Guardian)?,
+    })
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map};
 use injective_cosmwasm::{MarketId, OracleType, SubaccountId};
 use injective_math::FPDecimal;
 use schemars::JsonSchema;
@@ -17,27 +17,239 @@ pub struct Config {
     pub quote_denom: String,
     pub fund_subaccount_id: SubaccountId,
     pub performance_fee_rate: FPDecimal,
+    pub management_fee_rate: FPDecimal, // yearly, e.g. 0.02 means 2% of the fund per year
     pub min_yearly_roi_for_fees: FPDecimal, // e.g. 1.1 means min 10% yearly ROI before paying admin fees
+    pub lockup_period: u64,                 // in seconds, each lot can only be redeemed after it
+    pub early_redemption_policy: Option<EarlyRedemptionPolicy>, // rejects early redemptions if not set
+    #[serde(default)]
+    pub performance_fee_collection: PerformanceFeeCollection,
+    #[serde(default)]
+    pub crystallization_period: u64, // in seconds, fees are only charged on redemption if zero
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PerformanceFeeCollection {
+    Quote, // taken from the quote funds first, the rest pro-rata from the other assets
+    #[default]
+    ProRata, // taken pro-rata from every asset with a positive value
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct LPPosition {
+pub struct EarlyRedemptionPenaltyTier {
+    pub min_remaining_lockup: u64, // in seconds, applies while at least this much lockup remains
+    pub penalty_rate: FPDecimal,   // share of the redeemed notional kept as penalty
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EarlyRedemptionPolicy {
+    pub penalty_tiers: Vec<EarlyRedemptionPenaltyTier>, // one of them with min_remaining_lockup 0
+    // receives the coin part of penalties, penalties stay in the fund for the other LPs if not
+    // set. The derivative position part of a penalty always stays in the fund, since a redemption
+    // can only transfer positions to the redeemer.
+    pub penalty_recipient: Option<Addr>,
+}
+
+impl EarlyRedemptionPolicy {
+    pub fn get_penalty_rate(&self, remaining_lockup: u64) -> FPDecimal {
+        self.penalty_tiers
+            .iter()
+            .filter(|tier| remaining_lockup >= tier.min_remaining_lockup)
+            .max_by_key(|tier| tier.min_remaining_lockup)
+            .map(|tier| tier.penalty_rate)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LPLot {
+    pub lot_id: u64,
     pub shares: FPDecimal,
     pub subscription_time: Timestamp,
     pub subscription_amount: FPDecimal,
+    #[serde(default)]
+    pub high_water_mark: FPDecimal, // NAV per share at subscription, see get_high_water_mark
+}
+
+impl LPLot {
+    /// Performance fees are only charged above the higher of the lot mark and the fund mark,
+    /// since crystallizations already charged the gains up to the fund mark.
+    pub fn get_high_water_mark(&self, fund_high_water_mark: FPDecimal) -> FPDecimal {
+        self.high_water_mark.max(fund_high_water_mark)
+    }
+
+    pub fn get_profits_above_high_water_mark(
+        &self,
+        fund_high_water_mark: FPDecimal,
+        shares: FPDecimal,
+        profits: FPDecimal,
+        net_redemption_notional: FPDecimal,
+    ) -> FPDecimal {
+        let gains_above_peak =
+            net_redemption_notional - shares * self.get_high_water_mark(fund_high_water_mark);
+        profits.min(gains_above_peak).max(FPDecimal::zero())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct LPPosition {
+    pub lots: Vec<LPLot>, // oldest first
+}
+
+impl LPPosition {
+    pub fn shares(&self) -> FPDecimal {
+        self.lots
+            .iter()
+            .fold(FPDecimal::zero(), |total, lot| total + lot.shares)
+    }
+
+    pub fn subscription_amount(&self) -> FPDecimal {
+        self.lots.iter().fold(FPDecimal::zero(), |total, lot| {
+            total + lot.subscription_amount
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LpToken {
+    Tokenfactory { denom: String },
+    Cw20 { address: Addr },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Crystallization {
+    pub id: u64,
+    pub time: Timestamp,
+    pub nav_per_share: FPDecimal, // after the fee shares were minted
+    pub lp_total_supply: FPDecimal,
+    pub management_fee_shares: FPDecimal,
+    pub performance_fee_shares: FPDecimal,
+    pub performance_fee_notional: FPDecimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MarketExposure {
+    pub market_id: MarketId,
+    pub notional: FPDecimal, // in quote, spot balances at the oracle price or the derivative position value
+    #[serde(default)]
+    pub exposure: FPDecimal, // signed, the spot notional or the derivative quantity at the mark price
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NavSnapshot {
+    pub time: Timestamp,
+    pub nav: FPDecimal,
+    pub nav_per_share: FPDecimal,
+    pub lp_total_supply: FPDecimal,
+    pub market_exposures: Vec<MarketExposure>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingAdmin {
+    pub address: Addr,
+    pub expiry: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Trader,   // executes trading messages for the fund
+    Operator, // updates the fund config and receives the admin fee positions
+    Guardian, // pauses and closes the fund
+}
+
+// unassigned roles are held by the admin
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct Roles {
+    pub trader: Option<Addr>,
+    pub operator: Option<Addr>,
+    pub guardian: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PauseState {
+    pub reason: String,
+    pub paused_at: Timestamp,
+    pub block_redemptions: bool, // subscriptions and trading are always blocked
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MarketQuantityCap {
+    pub market_id: MarketId,
+    pub max_quantity: FPDecimal, // summed over the orders created in one trade
+}
+
+// checked for every order the trader creates, in addition to the fund markets and subaccount
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct RiskPolicy {
+    // max relative distance of an order price from the spot oracle or derivative mark price
+    #[serde(default)]
+    pub max_price_deviation: Option<FPDecimal>,
+    #[serde(default)]
+    pub market_quantity_caps: Vec<MarketQuantityCap>,
+    // limits relative to NAV on the exposure projected after the new orders fill
+    #[serde(default)]
+    pub max_leverage: Option<FPDecimal>, // gross exposure over all markets
+    #[serde(default)]
+    pub max_market_concentration: Option<FPDecimal>, // exposure in any single market
+    #[serde(default)]
+    pub max_derivative_notional_ratio: Option<FPDecimal>, // gross exposure over derivative markets
+    // drawdown of the NAV per share from its peak that trips the breaker, below 1
+    #[serde(default)]
+    pub max_drawdown: Option<FPDecimal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DrawdownTrip {
+    pub tripped_at: Timestamp,
+    pub peak_nav_per_share: FPDecimal,
+    pub nav_per_share: FPDecimal, // when the breaker tripped
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-pub const LP_POSITIONS: Item<HashMap<Addr, LPPosition>> = Item::new("lp_positions");
+pub const ROLES: Item<Roles> = Item::new("roles");
+
+pub const PENDING_ADMIN: Item<PendingAdmin> = Item::new("pending_admin");
+
+pub const LP_POSITIONS: Map<&Addr, LPPosition> = Map::new("lp_position");
+
+pub const LP_LOT_COUNT: Item<u64> = Item::new("lp_lot_count");
+
+pub const LAST_MANAGEMENT_FEE_ACCRUAL: Item<Timestamp> = Item::new("last_management_fee_accrual");
+
+pub const LAST_CRYSTALLIZATION_TIME: Item<Timestamp> = Item::new("last_crystallization_time");
+
+pub const CRYSTALLIZATION_COUNT: Item<u64> = Item::new("crystallization_count");
+
+pub const CRYSTALLIZATIONS: Map<u64, Crystallization> = Map::new("crystallizations");
+
+pub const NAV_HISTORY: Map<u64, NavSnapshot> = Map::new("nav_history"); // by block time in seconds
 
 pub const LP_TOTAL_SUPPLY: Item<FPDecimal> = Item::new("lp_total_supply");
 
-pub const ADMIN_FEE_POSITIONS: Item<HashMap<MarketId, FPDecimal>> =
-    Item::new("admin_fee_positions");
+// not set for funds created before LP tokens existed, those only track shares in LP_POSITIONS
+pub const LP_TOKEN: Item<LpToken> = Item::new("lp_token");
+
+pub const ADMIN_FEE_POSITIONS: Map<&str, FPDecimal> = Map::new("admin_fee_position"); // by market id
+
+pub const FUND_HIGH_WATER_MARK: Item<FPDecimal> = Item::new("fund_high_water_mark"); // NAV per share
+
+// shares of the lots charged performance fees whose mark is above the fund mark, by mark key
+pub const LOT_HIGH_WATER_MARKS: Map<u128, FPDecimal> = Map::new("lot_high_water_marks");
 
+// with an LP token the fund holds the tokens of these shares, see get_lp_token_mint_messages
 pub const ADMIN_OWNED_SHARES: Item<FPDecimal> = Item::new("admin_owned_shares");
 
 pub const DENOM_DECIMALS: Item<HashMap<String, u64>> = Item::new("denom_decimals");
 
 pub const IS_FUND_CLOSED: Item<bool> = Item::new("is_fund_closed");
+
+pub const RISK_POLICY: Item<RiskPolicy> = Item::new("risk_policy");
+
+pub const PEAK_NAV_PER_SHARE: Item<FPDecimal> = Item::new("peak_nav_per_share");
+
+pub const DRAWDOWN_TRIP: Item<DrawdownTrip> = Item::new("drawdown_trip"); // only set while tripped
+
+pub const PAUSE_STATE: Item<PauseState> = Item::new("pause_state"); // only set while paused